@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+/// Exponential backoff used when re-establishing a dropped IPC connection.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    /// Delay before the first retry.
+    pub initial: Duration,
+    /// Upper bound for a single delay.
+    pub max: Duration,
+    /// Multiplier applied to the delay after every failed attempt.
+    pub factor: u32,
+    /// Number of retries before giving up, `None` retries forever.
+    pub max_retries: Option<u32>,
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            ..Default::default()
+        }
+    }
+
+    pub fn factor(mut self, factor: u32) -> Self {
+        self.factor = factor;
+        self
+    }
+
+    pub fn max_retries(mut self, max_retries: Option<u32>) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Returns the sequence of delays to wait between attempts.
+    pub fn delays(&self) -> impl Iterator<Item = Duration> {
+        let max = self.max;
+        let factor = self.factor.max(1);
+        let retries = self.max_retries.map(|n| n as usize).unwrap_or(usize::MAX);
+
+        std::iter::successors(Some(self.initial.min(max)), move |delay| {
+            Some(delay.saturating_mul(factor).min(max))
+        })
+        .take(retries)
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(500),
+            max: Duration::from_secs(30),
+            factor: 2,
+            max_retries: Some(5),
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! Synchronous client for programs without an async runtime.
+//!
+//! The client only does I/O while one of its methods is running, so there
+//! is no background reconnect: events that arrive while waiting for a
+//! response are buffered until [`DiscordIPCClient::next_event`] is called.
+//! Once the connection is lost, or a frame could not be read, every later
+//! call fails with [`DiscordRPCError::ConnectionLost`].
+
+use crate::errors::DiscordRPCError;
+use crate::find_pipe;
+use crate::models::commands::{EventFunctionPayload, SetActivityArgs};
+use crate::models::events::{Config, EventPayload, ReadyData};
+use crate::models::rpc_command::RPCCommand;
+use crate::models::shared::User;
+use crate::protocol::{Session, SessionEvent, SessionState, DEFAULT_MAX_PAYLOAD};
+use crate::Result;
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::net::Shutdown;
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Size of the chunks read from the socket.
+const READ_BUFFER_SIZE: usize = 4096;
+
+pub struct DiscordIPCClient {
+    pub client_id: String,
+    stream: UnixStream,
+    session: Session,
+    ready: ReadyData,
+    /// Events received while waiting for a response.
+    events: VecDeque<String>,
+}
+
+/// Builder for [`DiscordIPCClient`], see [`DiscordIPCClient::builder`].
+pub struct DiscordIPCClientBuilder {
+    client_id: String,
+    socket_path: Option<PathBuf>,
+    timeout: Option<Duration>,
+    max_payload: usize,
+}
+
+impl DiscordIPCClient {
+    /// Connects to the first Discord IPC socket found.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let ipc_client = discord_rpc::blocking::DiscordIPCClient::new("<some client id>")?;
+    /// ```
+    pub fn new(client_id: &str) -> Result<Self> {
+        Self::builder(client_id).connect()
+    }
+
+    /// Returns a builder to configure the client before connecting.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let ipc_client = discord_rpc::blocking::DiscordIPCClient::builder("<some client id>")
+    ///     .timeout(Duration::from_secs(5))
+    ///     .connect()?;
+    /// ```
+    pub fn builder(client_id: &str) -> DiscordIPCClientBuilder {
+        DiscordIPCClientBuilder {
+            client_id: client_id.to_string(),
+            socket_path: None,
+            timeout: None,
+            max_payload: DEFAULT_MAX_PAYLOAD,
+        }
+    }
+
+    /// Returns the `READY` event Discord sent for the handshake.
+    pub fn ready(&self) -> &ReadyData {
+        &self.ready
+    }
+
+    /// Returns the user logged into the Discord client.
+    pub fn user(&self) -> &User {
+        &self.ready.user
+    }
+
+    /// Returns the configuration of the Discord client, such as its CDN host
+    /// and environment.
+    pub fn config(&self) -> &Config {
+        &self.ready.config
+    }
+
+    /// Returns the url of the logged in user's avatar.
+    pub fn avatar_url(&self) -> String {
+        self.ready.user.avatar_url(&self.ready.config.cdn_host)
+    }
+
+    /// Sends a command without waiting for its response.
+    pub fn emit_command(&mut self, command: &RPCCommand) -> Result<()> {
+        self.check_connected()?;
+        self.session.send_command(command)?;
+        self.flush()
+    }
+
+    /// Sends a command and blocks until Discord responds to it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiscordRPCError::Rpc`] if Discord answered with an `ERROR`
+    /// event, or an `Err` variant if the connection failed.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let activity = Activity::new().details("Frieren".to_string());
+    /// client.request(&RPCCommand::SetActivity(SetActivityArgs::new(activity)))?;
+    /// ```
+    pub fn request(&mut self, command: &RPCCommand) -> Result<EventFunctionPayload> {
+        self.check_connected()?;
+        let nonce = self.session.send_command(command)?;
+        self.flush()?;
+
+        loop {
+            match self.next_session_event()? {
+                SessionEvent::Response {
+                    nonce: response,
+                    result,
+                } if response == nonce => return result,
+                SessionEvent::Event(payload) => self.events.push_back(payload),
+                _ => {}
+            }
+        }
+    }
+
+    /// Blocks until the next event from a subscription arrives.
+    pub fn next_event(&mut self) -> Result<EventPayload> {
+        let payload = match self.events.pop_front() {
+            Some(payload) => payload,
+            None => loop {
+                if let SessionEvent::Event(payload) = self.next_session_event()? {
+                    break payload;
+                }
+            },
+        };
+
+        Ok(serde_json::from_str(&payload)?)
+    }
+
+    /// Clears the activity and closes the connection to Discord.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Err` variant if the `Close` frame could not be sent, or
+    /// else if the activity could not be cleared.
+    pub fn close(mut self) -> Result<()> {
+        let clear = RPCCommand::SetActivity(SetActivityArgs::default());
+        let cleared = self.request(&clear);
+
+        self.session.close();
+        self.flush()?;
+        self.stream.shutdown(Shutdown::Write)?;
+
+        cleared.map(drop)
+    }
+
+    fn next_session_event(&mut self) -> Result<SessionEvent> {
+        self.check_connected()?;
+        next_session_event(&mut self.stream, &mut self.session)
+    }
+
+    /// Fails once the session ended, e.g. after a frame too large to skip
+    /// or a connection that closed mid-frame.
+    fn check_connected(&self) -> Result<()> {
+        if self.session.state() == SessionState::Closed {
+            return Err(DiscordRPCError::ConnectionLost);
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        flush(&mut self.stream, &mut self.session)
+    }
+}
+
+impl DiscordIPCClientBuilder {
+    /// Connects to the socket at `path` instead of searching for one.
+    pub fn socket_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.socket_path = Some(path.into());
+        self
+    }
+
+    /// Fails reads and writes that take longer than `timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the largest payload sent or accepted, in bytes. Frames above it
+    /// fail with [`DiscordRPCError::FrameTooLarge`].
+    pub fn max_payload(mut self, max_payload: usize) -> Self {
+        self.max_payload = max_payload;
+        self
+    }
+
+    /// Connects to Discord and waits for the handshake to complete.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiscordRPCError::HandshakeFailed`] if Discord rejected the
+    /// handshake, or an `Err` variant if no socket could be connected.
+    pub fn connect(self) -> Result<DiscordIPCClient> {
+        let path = find_pipe(self.socket_path.as_deref())?;
+        let mut stream = UnixStream::connect(path).map_err(|_| DiscordRPCError::CouldNotConnect)?;
+        stream.set_read_timeout(self.timeout)?;
+        stream.set_write_timeout(self.timeout)?;
+
+        let mut session = Session::with_max_payload(&self.client_id, self.max_payload);
+        session.handshake();
+        flush(&mut stream, &mut session)?;
+
+        let ready = loop {
+            if let SessionEvent::Ready(ready) = next_session_event(&mut stream, &mut session)? {
+                break ready;
+            }
+        };
+
+        Ok(DiscordIPCClient {
+            client_id: self.client_id,
+            stream,
+            session,
+            ready,
+            events: VecDeque::new(),
+        })
+    }
+}
+
+/// Reads from the socket until the session yields an event.
+fn next_session_event(stream: &mut UnixStream, session: &mut Session) -> Result<SessionEvent> {
+    let mut buf = [0u8; READ_BUFFER_SIZE];
+    loop {
+        let event = session.poll();
+        // pongs are queued while polling
+        flush(stream, session)?;
+        if let Some(event) = event? {
+            return Ok(event);
+        }
+
+        let read = stream.read(&mut buf)?;
+        if read == 0 {
+            session.finish()?;
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        session.receive(&buf[..read]);
+    }
+}
+
+/// Writes everything the session queued.
+fn flush(stream: &mut UnixStream, session: &mut Session) -> Result<()> {
+    let outgoing = session.take_outgoing();
+    if !outgoing.is_empty() {
+        stream.write_all(&outgoing)?;
+    }
+    Ok(())
+}
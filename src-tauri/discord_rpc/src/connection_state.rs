@@ -0,0 +1,30 @@
+use serde::Serialize;
+
+use crate::models::shared::User;
+
+/// What a [`DiscordIPCClient`](crate::DiscordIPCClient) is currently doing,
+/// see [`DiscordIPCClient::connection_state`](crate::DiscordIPCClient::connection_state).
+///
+/// Serializes with a `state` tag, e.g. `{ "state": "ready", "user": .. }`,
+/// so apps can forward it to a frontend as is.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ConnectionState {
+    /// The handshake succeeded and requests can be sent.
+    Ready { user: User },
+    /// The socket was lost and the client is trying to get it back.
+    Reconnecting {
+        /// Why the connection was lost, or why the last attempt failed.
+        reason: String,
+        /// Number of failed attempts so far.
+        attempts: u32,
+    },
+    /// The client will not connect again.
+    Closed { reason: String },
+}
+
+impl ConnectionState {
+    pub fn is_ready(&self) -> bool {
+        matches!(self, ConnectionState::Ready { .. })
+    }
+}
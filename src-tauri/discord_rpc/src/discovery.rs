@@ -0,0 +1,195 @@
+//! Finding every running Discord client and picking the ones to talk to.
+//!
+//! Stable, PTB and Canary can run side by side, each on its own IPC socket.
+//! [`discover`] handshakes with all of them so the user can tell them apart,
+//! and an [`EndpointPreference`] decides which ones [`connect_preferred`]
+//! connects to. The preference is serializable so apps can persist it.
+
+use crate::errors::DiscordRPCError;
+use crate::find_pipes;
+use crate::ipc_socket::DiscordIPCSocket;
+use crate::models::events::{Config, ReadyData};
+use crate::models::shared::User;
+use crate::protocol::Session;
+use crate::{DiscordIPCClient, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How long a single socket may take to answer the handshake.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Release channel of a Discord client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReleaseChannel {
+    Stable,
+    Ptb,
+    Canary,
+    Development,
+}
+
+impl ReleaseChannel {
+    /// Tells the channel from the api endpoint a client reports in `READY`.
+    pub fn from_config(config: &Config) -> Self {
+        let host = config.api_endpoint.trim_start_matches("https:");
+        if host.starts_with("//canary.") {
+            ReleaseChannel::Canary
+        } else if host.starts_with("//ptb.") {
+            ReleaseChannel::Ptb
+        } else if config.environment == "production" {
+            ReleaseChannel::Stable
+        } else {
+            ReleaseChannel::Development
+        }
+    }
+}
+
+/// Outcome of [`discover`]: the clients that answered, and why every other
+/// socket was skipped.
+#[derive(Debug, Default)]
+pub struct Discovery {
+    pub endpoints: Vec<Endpoint>,
+    pub skipped: Vec<(PathBuf, DiscordRPCError)>,
+}
+
+/// A live IPC socket together with what its client reported on connect.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Endpoint {
+    pub path: PathBuf,
+    pub ready: ReadyData,
+}
+
+impl Endpoint {
+    pub fn release_channel(&self) -> ReleaseChannel {
+        ReleaseChannel::from_config(&self.ready.config)
+    }
+
+    pub fn environment(&self) -> &str {
+        &self.ready.config.environment
+    }
+
+    /// The user logged into this client.
+    pub fn user(&self) -> &User {
+        &self.ready.user
+    }
+}
+
+/// Which of the discovered clients to connect to.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum EndpointPreference {
+    /// The first client found, matching the behaviour of [`DiscordIPCClient::new`].
+    #[default]
+    First,
+    /// The first client of a release channel.
+    Channel(ReleaseChannel),
+    /// The client listening on a specific socket.
+    Path(PathBuf),
+    /// Every client, so the presence is mirrored to all of them.
+    All,
+}
+
+impl EndpointPreference {
+    /// Picks the preferred endpoints out of `endpoints`.
+    ///
+    /// If the preferred client is not running, the first endpoint is used
+    /// instead so the presence still shows up somewhere.
+    pub fn select<'a>(&self, endpoints: &'a [Endpoint]) -> Vec<&'a Endpoint> {
+        let preferred = match self {
+            EndpointPreference::First => None,
+            EndpointPreference::Channel(channel) => endpoints
+                .iter()
+                .find(|endpoint| endpoint.release_channel() == *channel),
+            EndpointPreference::Path(path) => {
+                endpoints.iter().find(|endpoint| endpoint.path == *path)
+            }
+            EndpointPreference::All => return endpoints.iter().collect(),
+        };
+
+        preferred
+            .or_else(|| endpoints.first())
+            .into_iter()
+            .collect()
+    }
+}
+
+/// Handshakes with every Discord IPC socket and returns the ones that
+/// answered with `READY`.
+///
+/// Sockets left behind by crashed clients, or clients that reject
+/// `client_id`, are skipped and listed in [`Discovery::skipped`]. A socket
+/// that does not answer in time is skipped with [`DiscordRPCError::Timeout`].
+///
+/// # Errors
+///
+/// Returns [`DiscordRPCError::PipeNotFound`] if no socket exists at all.
+///
+/// # Examples
+/// ```ignore
+/// for endpoint in discover("<some client id>").await?.endpoints {
+///     println!("{:?} as {}", endpoint.release_channel(), endpoint.user().username);
+/// }
+/// ```
+pub async fn discover(client_id: &str) -> Result<Discovery> {
+    Ok(discover_in(client_id, find_pipes(None)?).await)
+}
+
+/// Like [`discover`], but only probes the sockets at `paths`.
+pub async fn discover_in(client_id: &str, paths: Vec<PathBuf>) -> Discovery {
+    let mut discovery = Discovery::default();
+    for path in paths {
+        match tokio::time::timeout(PROBE_TIMEOUT, probe(client_id, &path)).await {
+            Ok(Ok(ready)) => discovery.endpoints.push(Endpoint { path, ready }),
+            Ok(Err(e)) => discovery.skipped.push((path, e)),
+            Err(_) => discovery.skipped.push((path, DiscordRPCError::Timeout)),
+        }
+    }
+    discovery
+}
+
+/// Connects a client to every endpoint `preference` selects.
+///
+/// # Errors
+///
+/// Returns [`DiscordRPCError::CouldNotConnect`] if no client answered, or
+/// the error of the first failed connection.
+pub async fn connect_preferred(
+    client_id: &str,
+    preference: &EndpointPreference,
+) -> Result<Vec<DiscordIPCClient>> {
+    let discovery = discover(client_id).await?;
+    connect_selected(client_id, preference, &discovery.endpoints).await
+}
+
+/// Connects a client to every endpoint of `endpoints` that `preference`
+/// selects, see [`connect_preferred`].
+pub async fn connect_selected(
+    client_id: &str,
+    preference: &EndpointPreference,
+    endpoints: &[Endpoint],
+) -> Result<Vec<DiscordIPCClient>> {
+    let selected = preference.select(endpoints);
+    if selected.is_empty() {
+        return Err(DiscordRPCError::CouldNotConnect);
+    }
+
+    let mut clients = Vec::new();
+    for endpoint in selected {
+        let client = DiscordIPCClient::builder(client_id)
+            .socket_path(&endpoint.path)
+            .connect()
+            .await?;
+        clients.push(client);
+    }
+    Ok(clients)
+}
+
+async fn probe(client_id: &str, path: &Path) -> Result<ReadyData> {
+    let mut socket = DiscordIPCSocket::new(Some(path.to_path_buf())).await?;
+    let mut session = Session::new(client_id);
+    let ready = socket.handshake(&mut session).await?;
+    // the probe is done, a failing close does not change the result
+    let _ = socket.close(&mut session).await;
+    Ok(ready)
+}
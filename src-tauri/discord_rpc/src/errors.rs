@@ -1,12 +1,14 @@
-use std::{array::TryFromSliceError, string::FromUtf8Error};
+use std::{array::TryFromSliceError, fmt, io, path::PathBuf, string::FromUtf8Error};
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use tokio::io;
 
 #[derive(Error, Debug)]
 pub enum DiscordRPCError {
     #[error("Could not connect to Discord")]
     CouldNotConnect,
+    #[error("Could not find a Discord IPC socket, searched {0:?}")]
+    PipeNotFound(Vec<PathBuf>),
     #[error("Failed to convert from slice")]
     TryFromSlice(#[from] TryFromSliceError),
     #[error("An I/O error occurred")]
@@ -15,4 +17,145 @@ pub enum DiscordRPCError {
     FromUtf8(#[from] FromUtf8Error),
     #[error("A serde_json error occurred")]
     SerdeJson(#[from] serde_json::Error),
+    #[error("Received unknown opcode {0}")]
+    UnknownOpcode(u32),
+    #[error("Frame of {length} bytes exceeds the maximum payload of {max} bytes")]
+    FrameTooLarge { length: usize, max: usize },
+    #[error("Connection ended {received} bytes into a frame of {expected} bytes")]
+    TruncatedFrame { expected: usize, received: usize },
+    #[error("Discord closed the connection ({code}): {message}")]
+    Closed { code: u32, message: String },
+    #[error("Discord rejected the handshake ({code}): {message}")]
+    HandshakeFailed { code: u32, message: String },
+    #[error("Discord returned {code}: {message}")]
+    Rpc {
+        code: RpcErrorCode,
+        message: String,
+        /// Nonce of the request that failed, if Discord sent one back
+        nonce: Option<String>,
+    },
+    #[error("The connection to Discord was lost")]
+    ConnectionLost,
+    #[error("The client has been closed")]
+    ClientClosed,
+    #[error("Discord did not respond in time")]
+    Timeout,
+    #[error("Invalid activity: {}", list_fields(.0))]
+    InvalidActivity(Vec<InvalidField>),
+    #[error("Discord returned an unexpected response")]
+    UnexpectedResponse,
+    #[error("Token exchange failed: {0}")]
+    TokenExchange(String),
+}
+
+/// Error codes Discord sends in the `data` of an `ERROR` event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "u32", into = "u32")]
+pub enum RpcErrorCode {
+    UnknownError,
+    /// The command was malformed, e.g. an activity Discord rejected
+    InvalidPayload,
+    InvalidCommand,
+    InvalidGuild,
+    InvalidEvent,
+    InvalidChannel,
+    /// The client is not authenticated or lacks the scope for the command
+    InvalidPermissions,
+    InvalidClientId,
+    InvalidOrigin,
+    InvalidToken,
+    InvalidUser,
+    OAuth2Error,
+    SelectChannelTimedOut,
+    GetGuildTimedOut,
+    /// The user is in another voice channel, retry with `force`
+    SelectVoiceForceRequired,
+    CaptureShortcutAlreadyListening,
+    /// A code that is not documented yet
+    Other(u32),
+}
+
+impl RpcErrorCode {
+    pub fn code(self) -> u32 {
+        self.into()
+    }
+}
+
+impl From<u32> for RpcErrorCode {
+    fn from(code: u32) -> Self {
+        match code {
+            1000 => RpcErrorCode::UnknownError,
+            4000 => RpcErrorCode::InvalidPayload,
+            4002 => RpcErrorCode::InvalidCommand,
+            4003 => RpcErrorCode::InvalidGuild,
+            4004 => RpcErrorCode::InvalidEvent,
+            4005 => RpcErrorCode::InvalidChannel,
+            4006 => RpcErrorCode::InvalidPermissions,
+            4007 => RpcErrorCode::InvalidClientId,
+            4008 => RpcErrorCode::InvalidOrigin,
+            4009 => RpcErrorCode::InvalidToken,
+            4010 => RpcErrorCode::InvalidUser,
+            5000 => RpcErrorCode::OAuth2Error,
+            5001 => RpcErrorCode::SelectChannelTimedOut,
+            5002 => RpcErrorCode::GetGuildTimedOut,
+            5003 => RpcErrorCode::SelectVoiceForceRequired,
+            5004 => RpcErrorCode::CaptureShortcutAlreadyListening,
+            code => RpcErrorCode::Other(code),
+        }
+    }
+}
+
+impl From<RpcErrorCode> for u32 {
+    fn from(code: RpcErrorCode) -> Self {
+        match code {
+            RpcErrorCode::UnknownError => 1000,
+            RpcErrorCode::InvalidPayload => 4000,
+            RpcErrorCode::InvalidCommand => 4002,
+            RpcErrorCode::InvalidGuild => 4003,
+            RpcErrorCode::InvalidEvent => 4004,
+            RpcErrorCode::InvalidChannel => 4005,
+            RpcErrorCode::InvalidPermissions => 4006,
+            RpcErrorCode::InvalidClientId => 4007,
+            RpcErrorCode::InvalidOrigin => 4008,
+            RpcErrorCode::InvalidToken => 4009,
+            RpcErrorCode::InvalidUser => 4010,
+            RpcErrorCode::OAuth2Error => 5000,
+            RpcErrorCode::SelectChannelTimedOut => 5001,
+            RpcErrorCode::GetGuildTimedOut => 5002,
+            RpcErrorCode::SelectVoiceForceRequired => 5003,
+            RpcErrorCode::CaptureShortcutAlreadyListening => 5004,
+            RpcErrorCode::Other(code) => code,
+        }
+    }
+}
+
+impl fmt::Display for RpcErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RpcErrorCode::Other(code) => write!(f, "error {}", code),
+            known => write!(f, "{:?} ({})", known, known.code()),
+        }
+    }
+}
+
+/// A field rejected by client-side validation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidField {
+    /// Path of the field, e.g. `buttons[0].url`
+    pub field: String,
+    pub reason: String,
+}
+
+impl fmt::Display for InvalidField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.field, self.reason)
+    }
+}
+
+fn list_fields(fields: &[InvalidField]) -> String {
+    fields
+        .iter()
+        .map(InvalidField::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
 }
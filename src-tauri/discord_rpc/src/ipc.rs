@@ -1,156 +1,1115 @@
-use crate::create_json;
-use crate::ipc_socket::DiscordIPCSocket;
-use crate::models::events::EventPayload;
+use crate::backoff::Backoff;
+use crate::connection_state::ConnectionState;
+use crate::errors::DiscordRPCError;
+use crate::ipc_socket::{DiscordIPCSocket, READ_BUFFER_SIZE};
+use crate::models::commands::{
+    AuthenticateArgs, AuthorizeArgs, ChannelData, ChannelsData, CloseActivityJoinRequestArgs,
+    EventFunctionPayload, GetChannelArgs, GetChannelsArgs, GetGuildArgs, GetImageArgs, GuildData,
+    GuildsData, OAuthRequest, OAuthScope, SelectTextChannelArgs, SelectVoiceChannelArgs,
+    SendActivityJoinInviteArgs, SetActivityArgs, SetVoiceSettingsArgs, TokenResponse,
+    VoiceSettings,
+};
+use crate::models::events::{AuthData, Config, ReadyData};
 use crate::models::rpc_command::RPCCommand;
-use crate::opcodes::OPCODES;
+use crate::models::rpc_event::RPCEvent;
+use crate::models::shared::User;
+use crate::presence_queue::{PresenceQueue, RateLimit};
+use crate::protocol::{Session, SessionEvent, SessionState, DEFAULT_MAX_PAYLOAD};
+use crate::subscription::{
+    EventStream, HandlerHandle, SubscriptionGuard, SubscriptionRegistry, SubscriptionToken,
+};
 use crate::EventReceive;
 use crate::Result;
-use serde::Deserialize;
-use serde::Serialize;
-use serde_json::json;
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, oneshot, watch};
+use tokio::task::JoinHandle;
+
+/// Number of unhandled frames buffered for slow event subscribers.
+const EVENT_CAPACITY: usize = 64;
+
+/// How long [`DiscordIPCClient::close`] waits for the activity to be cleared.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long a request waits for its response by default.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
 
 pub struct DiscordIPCClient {
     pub client_id: String,
+    connection: Connection,
+    worker: JoinHandle<()>,
+}
+
+type PendingRequests = HashMap<String, oneshot::Sender<Result<EventFunctionPayload>>>;
+
+/// What a [`Connection`] asks its [`Worker`] to do. Each message carries
+/// the sender its outcome is reported to.
+enum Message {
+    /// Sends a command, answered with Discord's response to it.
+    Request {
+        command: RPCCommand,
+        reply: oneshot::Sender<Result<EventFunctionPayload>>,
+    },
+    /// Sends a command, answered once it was written.
+    Emit {
+        command: RPCCommand,
+        sent: oneshot::Sender<Result<()>>,
+    },
+    /// Sends an already serialized payload, answered once it was written.
+    EmitPayload {
+        payload: String,
+        sent: oneshot::Sender<Result<()>>,
+    },
+    Reconnect {
+        reason: String,
+        done: oneshot::Sender<Result<()>>,
+    },
+    Close {
+        done: oneshot::Sender<Result<()>>,
+    },
+}
+
+/// Handle to the [`Worker`] owning the socket, shared between the client
+/// and everything it hands out, such as streams and presence queues.
+#[derive(Clone)]
+pub(crate) struct Connection {
+    messages: mpsc::UnboundedSender<Message>,
+    request_timeout: Duration,
+    /// Raw frames that did not answer a pending request.
+    events: broadcast::Sender<String>,
+    /// Failures nobody awaits, such as undecodable frames or a lost socket.
+    errors: broadcast::Sender<Arc<DiscordRPCError>>,
+    /// The subscribed events, subscribed again after a reconnect.
+    subscriptions: Arc<StdMutex<SubscriptionRegistry>>,
+    /// Set once the client was closed, stops every background task.
+    closed: Arc<watch::Sender<bool>>,
+    /// The `READY` event of the latest handshake.
+    ready: Arc<StdMutex<ReadyData>>,
+    /// What the client is doing, see [`DiscordIPCClient::connection_state`].
+    state: Arc<watch::Sender<ConnectionState>>,
+}
+
+/// Background task driving the [`Session`] over the socket. It is the only
+/// one touching either, so frames, reconnects and closing never race.
+struct Worker {
     socket: DiscordIPCSocket,
+    session: Session,
+    backoff: Backoff,
+    messages: mpsc::UnboundedReceiver<Message>,
+    connection: Connection,
+    /// Requests waiting for the response carrying their nonce.
+    pending: PendingRequests,
+    /// The last `SET_ACTIVITY` command, replayed after a reconnect.
+    last_activity: Option<RPCCommand>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct OAuthRequest<'a> {
-    client_id: &'a str,
-    code: &'a str,
-    grant_type: &'a str,
+/// Builder for [`DiscordIPCClient`], see [`DiscordIPCClient::builder`].
+pub struct DiscordIPCClientBuilder {
+    client_id: String,
+    backoff: Backoff,
+    socket_path: Option<PathBuf>,
+    max_payload: usize,
+    request_timeout: Duration,
 }
 
 impl DiscordIPCClient {
     /// Creates a new `DiscordIPCClient`.
     ///
     /// # Examples
-    /// ```
-    /// let ipc_client = DiscordIPCClient::new("<some client id>")?;
+    /// ```ignore
+    /// let ipc_client = DiscordIPCClient::new("<some client id>").await?;
     /// ```
     pub async fn new(client_id: &str) -> Result<Self> {
-        let socket = DiscordIPCSocket::new().await?;
+        Self::builder(client_id).connect().await
+    }
 
-        let mut client = Self {
+    /// Returns a builder for a `DiscordIPCClient` with custom connection settings.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let ipc_client = DiscordIPCClient::builder("<some client id>")
+    ///     .backoff(Backoff::new(Duration::from_secs(1), Duration::from_secs(60)))
+    ///     .socket_path("/run/user/1000/discord-ipc-0")
+    ///     .connect()
+    ///     .await?;
+    /// ```
+    pub fn builder(client_id: &str) -> DiscordIPCClientBuilder {
+        DiscordIPCClientBuilder {
             client_id: client_id.to_string(),
-            socket,
-        };
+            backoff: Backoff::default(),
+            socket_path: None,
+            max_payload: DEFAULT_MAX_PAYLOAD,
+            request_timeout: REQUEST_TIMEOUT,
+        }
+    }
 
-        // connect to client
-        client.connect().await?;
+    /// Reconnects the client to the Discord IPC.
+    ///
+    /// The IPC socket is looked up again and a new handshake is sent,
+    /// retrying with the client's [`Backoff`] until it succeeds or the
+    /// retries run out. The last activity set through [`emit_command`]
+    /// is replayed once the connection is back.
+    ///
+    /// This is called automatically whenever a send or the background
+    /// reader notices that the socket was dropped. Requests still waiting
+    /// for a response fail with [`DiscordRPCError::ConnectionLost`].
+    ///
+    /// [`emit_command`]: #method.emit_command
+    ///
+    /// # Errors
+    ///
+    /// Returns the last connection error if every attempt failed. The
+    /// client is closed then, and [`connection_state`] says why.
+    ///
+    /// [`connection_state`]: #method.connection_state
+    pub async fn reconnect(&self) -> Result<()> {
+        self.connection.reconnect("requested by the caller").await
+    }
 
-        Ok(client)
+    /// Returns a receiver that is notified whenever the client connects,
+    /// loses the connection or is closed.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let mut state = client.connection_state();
+    /// while state.changed().await.is_ok() {
+    ///     match &*state.borrow() {
+    ///         ConnectionState::Ready { user } => println!("connected as {}", user.username),
+    ///         ConnectionState::Reconnecting { reason, .. } => println!("lost discord: {}", reason),
+    ///         other => println!("{:?}", other),
+    ///     }
+    /// }
+    /// ```
+    pub fn connection_state(&self) -> watch::Receiver<ConnectionState> {
+        self.connection.state.subscribe()
     }
 
-    /// Connects the client to the Discord IPC.
+    /// Returns the `READY` event Discord sent for the latest handshake.
+    pub fn ready(&self) -> ReadyData {
+        self.connection.ready()
+    }
+
+    /// Returns the user logged into the Discord client.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let user = ipc_client.user();
+    /// println!("Connected as {}", user.username);
+    /// ```
+    pub fn user(&self) -> User {
+        self.ready().user
+    }
+
+    /// Returns the configuration of the Discord client, such as its CDN host
+    /// and environment.
+    pub fn config(&self) -> Config {
+        self.ready().config
+    }
+
+    /// Returns the url of the logged in user's avatar.
+    pub fn avatar_url(&self) -> String {
+        let ready = self.ready();
+        ready.user.avatar_url(&ready.config.cdn_host)
+    }
+
+    /// send a json string payload to the socket
+    pub async fn emit_string(&self, payload: String) -> Result<()> {
+        self.connection.emit_payload(payload).await
+    }
+
+    /// send a json string payload to the socket
+    pub async fn emit_command(&self, command: &RPCCommand) -> Result<()> {
+        self.connection.emit(command).await
+    }
+
+    /// Sends a command and waits for Discord's response to it.
     ///
-    /// This method attempts to first establish a connection,
-    /// and then sends a handshake.
+    /// The response is matched to the command by its nonce, any other
+    /// frames received in the meantime are passed on to the [`handler`]s.
+    ///
+    /// [`handler`]: #method.handler
     ///
     /// # Errors
     ///
-    /// Returns an `Err` variant if the client
-    /// fails to connect to the socket, or if it fails to
-    /// send a handshake.
+    /// Returns [`DiscordRPCError::Rpc`] if Discord answered with an `ERROR`
+    /// event, [`DiscordRPCError::ConnectionLost`] if the connection dropped
+    /// before the response arrived, or [`DiscordRPCError::Timeout`] if it
+    /// did not arrive within the [`request_timeout`].
+    ///
+    /// [`request_timeout`]: DiscordIPCClientBuilder::request_timeout
     ///
     /// # Examples
+    /// ```ignore
+    /// let activity = Activity::new().details("Frieren".to_string());
+    /// client.request(&RPCCommand::SetActivity(SetActivityArgs::new(activity))).await?;
     /// ```
-    /// let mut client = discord_ipc::new_client("<some client id>")?;
-    /// client.connect()?;
+    pub async fn request(&self, command: &RPCCommand) -> Result<EventFunctionPayload> {
+        self.connection.request(command).await
+    }
+
+    /// Asks the user to authorize this application for `scopes`.
+    ///
+    /// Discord shows a consent modal and, once accepted, returns an
+    /// authorization code that can be exchanged for an access token.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiscordRPCError::Rpc`] if the user rejected the request.
+    pub async fn authorize(&self, scopes: Vec<OAuthScope>) -> Result<String> {
+        let args = AuthorizeArgs::new(&self.client_id, scopes);
+        match self.request(&RPCCommand::Authorize(args)).await? {
+            EventFunctionPayload::Authorize { data } => Ok(data.code),
+            _ => Err(DiscordRPCError::UnexpectedResponse),
+        }
+    }
+
+    /// Exchanges an authorization code for an access token.
+    ///
+    /// The HTTP call itself is left to `exchange`, which receives the
+    /// request body for Discord's `/oauth2/token` endpoint. This keeps
+    /// the client secret and the HTTP stack outside of this crate.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let code = client.authorize(vec![OAuthScope::Rpc, OAuthScope::Identify]).await?;
+    /// let token = client
+    ///     .exchange_code(&code, |request| async move { my_backend.token(request).await })
+    ///     .await?;
     /// ```
-    async fn connect(&mut self) -> Result<()> {
-        println!("Connecting to client...");
+    pub async fn exchange_code<F, Fut>(&self, code: &str, exchange: F) -> Result<TokenResponse>
+    where
+        F: FnOnce(OAuthRequest) -> Fut,
+        Fut: Future<Output = Result<TokenResponse>>,
+    {
+        exchange(OAuthRequest {
+            client_id: self.client_id.clone(),
+            code: code.to_string(),
+            grant_type: "authorization_code".to_string(),
+        })
+        .await
+    }
 
-        self.send_handshake().await?;
+    /// Authenticates the connection with an OAuth2 access token.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiscordRPCError::Rpc`] if the token was rejected.
+    pub async fn authenticate(&self, access_token: &str) -> Result<AuthData> {
+        let args = AuthenticateArgs::new(access_token);
+        match self.request(&RPCCommand::Authenticate(args)).await? {
+            EventFunctionPayload::Authenticate { data } => Ok(data),
+            _ => Err(DiscordRPCError::UnexpectedResponse),
+        }
+    }
 
-        // TODO: handle error
-        let (_opcode, payload) = self.socket.recv().await.unwrap();
+    /// Runs the whole OAuth2 flow: [`authorize`], [`exchange_code`]
+    /// and [`authenticate`].
+    ///
+    /// [`authorize`]: #method.authorize
+    /// [`exchange_code`]: #method.exchange_code
+    /// [`authenticate`]: #method.authenticate
+    pub async fn login<F, Fut>(&self, scopes: Vec<OAuthScope>, exchange: F) -> Result<AuthData>
+    where
+        F: FnOnce(OAuthRequest) -> Fut,
+        Fut: Future<Output = Result<TokenResponse>>,
+    {
+        let code = self.authorize(scopes).await?;
+        let token = self.exchange_code(&code, exchange).await?;
+        self.authenticate(&token.access_token).await
+    }
 
-        // spooky line is not working
-        let payload = serde_json::from_str(&payload)?;
-        match payload {
-            EventPayload::Ready { .. } => {
-                println!("Connected to discord and got ready event!");
-            }
-            _ => {
-                println!("Could not connect to discord...");
+    /// Fetches a guild the user is in.
+    pub async fn get_guild(&self, args: GetGuildArgs) -> Result<GuildData> {
+        match self.request(&RPCCommand::GetGuild(args)).await? {
+            EventFunctionPayload::GetGuild { data } => Ok(data),
+            _ => Err(DiscordRPCError::UnexpectedResponse),
+        }
+    }
+
+    /// Lists the guilds the user is in.
+    pub async fn get_guilds(&self) -> Result<GuildsData> {
+        match self.request(&RPCCommand::GetGuilds).await? {
+            EventFunctionPayload::GetGuilds { data } => Ok(data),
+            _ => Err(DiscordRPCError::UnexpectedResponse),
+        }
+    }
+
+    /// Fetches a channel the user has access to.
+    pub async fn get_channel(&self, args: GetChannelArgs) -> Result<ChannelData> {
+        match self.request(&RPCCommand::GetChannel(args)).await? {
+            EventFunctionPayload::GetChannel { data } => Ok(data),
+            _ => Err(DiscordRPCError::UnexpectedResponse),
+        }
+    }
+
+    /// Lists the channels of a guild.
+    pub async fn get_channels(&self, args: GetChannelsArgs) -> Result<ChannelsData> {
+        match self.request(&RPCCommand::GetChannels(args)).await? {
+            EventFunctionPayload::GetChannels { data } => Ok(data),
+            _ => Err(DiscordRPCError::UnexpectedResponse),
+        }
+    }
+
+    /// Joins or leaves a voice channel, returning the joined channel.
+    pub async fn select_voice_channel(
+        &self,
+        args: SelectVoiceChannelArgs,
+    ) -> Result<Option<ChannelData>> {
+        match self.request(&RPCCommand::SelectVoiceChannel(args)).await? {
+            EventFunctionPayload::SelectVoiceChannel { data } => Ok(data),
+            _ => Err(DiscordRPCError::UnexpectedResponse),
+        }
+    }
+
+    /// Returns the voice channel the user is in, if any.
+    pub async fn get_selected_voice_channel(&self) -> Result<Option<ChannelData>> {
+        match self.request(&RPCCommand::GetSelectedVoiceChannel).await? {
+            EventFunctionPayload::GetSelectedVoiceChannel { data } => Ok(data),
+            _ => Err(DiscordRPCError::UnexpectedResponse),
+        }
+    }
+
+    /// Opens or leaves a text channel, returning the opened channel.
+    pub async fn select_text_channel(
+        &self,
+        args: SelectTextChannelArgs,
+    ) -> Result<Option<ChannelData>> {
+        match self.request(&RPCCommand::SelectTextChannel(args)).await? {
+            EventFunctionPayload::SelectTextChannel { data } => Ok(data),
+            _ => Err(DiscordRPCError::UnexpectedResponse),
+        }
+    }
+
+    /// Returns the user's voice settings.
+    pub async fn get_voice_settings(&self) -> Result<VoiceSettings> {
+        match self.request(&RPCCommand::GetVoiceSettings).await? {
+            EventFunctionPayload::GetVoiceSettings { data } => Ok(data),
+            _ => Err(DiscordRPCError::UnexpectedResponse),
+        }
+    }
+
+    /// Fetches an image, such as a user's avatar, as a `data:` uri.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let avatar = client
+    ///     .get_image(GetImageArgs::user(&client.user().id).size(64))
+    ///     .await?;
+    /// ```
+    pub async fn get_image(&self, args: GetImageArgs) -> Result<String> {
+        match self.request(&RPCCommand::GetImage(args)).await? {
+            EventFunctionPayload::GetImage { data } => Ok(data.data_uri),
+            _ => Err(DiscordRPCError::UnexpectedResponse),
+        }
+    }
+
+    /// Changes the user's voice settings, returning the updated settings.
+    ///
+    /// Only the fields set in `args` are changed.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let previous = client.get_voice_settings().await?;
+    /// client
+    ///     .set_voice_settings(SetVoiceSettingsArgs::new().mute(true))
+    ///     .await?;
+    /// // ... and once the episode is over
+    /// client.set_voice_settings((&previous).into()).await?;
+    /// ```
+    pub async fn set_voice_settings(&self, args: SetVoiceSettingsArgs) -> Result<VoiceSettings> {
+        match self.request(&RPCCommand::SetVoiceSettings(args)).await? {
+            EventFunctionPayload::SetVoiceSettings { data } => Ok(data),
+            _ => Err(DiscordRPCError::UnexpectedResponse),
+        }
+    }
+
+    /// Accepts a join request received through `ACTIVITY_JOIN_REQUEST`.
+    ///
+    /// The requesting user then receives `ACTIVITY_JOIN` with the
+    /// activity's join secret.
+    pub async fn accept_join_request(&self, user_id: &str) -> Result<()> {
+        let args = SendActivityJoinInviteArgs::new(user_id);
+        self.request(&RPCCommand::SendActivityJoinInvite(args))
+            .await?;
+        Ok(())
+    }
+
+    /// Rejects a join request received through `ACTIVITY_JOIN_REQUEST`.
+    pub async fn reject_join_request(&self, user_id: &str) -> Result<()> {
+        let args = CloseActivityJoinRequestArgs::new(user_id);
+        self.request(&RPCCommand::CloseActivityJoinRequest(args))
+            .await?;
+        Ok(())
+    }
+
+    /// Subscribes to `event` and returns a stream of its payloads.
+    ///
+    /// Dropping the stream unsubscribes again once no other stream
+    /// for the same event is left.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Err` variant if Discord rejected the subscription.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let mut speaking = client
+    ///     .subscribe(RPCEvent::SpeakingStart { channel_id })
+    ///     .await?;
+    /// while let Some(event) = speaking.next().await {
+    ///     println!("{:?}", event);
+    /// }
+    /// ```
+    pub async fn subscribe(&self, event: RPCEvent) -> Result<EventStream> {
+        let events = self.connection.events.subscribe();
+        let closed = self.connection.closed.subscribe();
+
+        // the stream is counted before SUBSCRIBE goes out, so a stream for
+        // the same event dropped meanwhile does not unsubscribe it
+        let (token, response) = {
+            let mut subscriptions = self.connection.subscriptions.lock().unwrap();
+            let token = subscriptions.add(&event)?;
+            match self.connection.queue_request(&RPCCommand::Subscribe(event)) {
+                Ok(response) => (token, response),
+                Err(e) => {
+                    subscriptions.release(&token);
+                    return Err(e);
+                }
             }
+        };
+        if let Err(e) = response.await {
+            self.connection
+                .subscriptions
+                .lock()
+                .unwrap()
+                .release(&token);
+            return Err(e);
         }
 
+        Ok(EventStream::new(
+            events,
+            closed,
+            SubscriptionGuard::new(self.connection.clone(), token),
+        ))
+    }
+
+    /// Unsubscribes from `event`, ending the delivery to every stream
+    /// returned by [`subscribe`] for it.
+    ///
+    /// [`subscribe`]: #method.subscribe
+    pub async fn unsubscribe(&self, event: RPCEvent) -> Result<()> {
+        let response = {
+            let mut subscriptions = self.connection.subscriptions.lock().unwrap();
+            subscriptions.remove(&event)?;
+            self.connection
+                .queue_request(&RPCCommand::Unsubscribe(event))?
+        };
+        response.await?;
         Ok(())
     }
 
-    /// Handshakes the Discord IPC.
+    /// Returns the events the client is subscribed to.
+    ///
+    /// They are subscribed again whenever the client reconnects, so the
+    /// streams keep receiving events across a Discord restart.
+    pub fn subscriptions(&self) -> Vec<RPCEvent> {
+        self.connection.subscriptions.lock().unwrap().events()
+    }
+
+    /// Returns a queue that sends activity updates within `limit`.
+    ///
+    /// Use it instead of [`request`] when the activity changes often, e.g.
+    /// on every play, pause and seek of a video.
+    ///
+    /// [`request`]: #method.request
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let queue = client.presence_queue(RateLimit::default());
+    /// queue.set(Activity::new().details("Frieren".to_string()))?;
+    /// let report = queue.flush().await;
+    /// ```
+    pub fn presence_queue(&self, limit: RateLimit) -> PresenceQueue {
+        PresenceQueue::new(self.connection.clone(), limit)
+    }
+
+    /// Closes the connection to Discord.
+    ///
+    /// The activity is cleared first, so the user's status disappears right
+    /// away, then a `Close` frame is sent and every background task of the
+    /// client, including [`handler`]s and [`EventStream`]s, is stopped.
+    /// Later calls on this client fail with [`DiscordRPCError::ClientClosed`].
+    ///
+    /// [`handler`]: #method.handler
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Err` variant if the `Close` frame could not be sent, or
+    /// else if the activity could not be cleared. The client is closed
+    /// regardless.
+    pub async fn close(&self) -> Result<()> {
+        if self.connection.is_closed() {
+            return Ok(());
+        }
+
+        let clear = RPCCommand::SetActivity(SetActivityArgs::default());
+        let cleared = tokio::time::timeout(CLOSE_TIMEOUT, self.request(&clear))
+            .await
+            .unwrap_or(Err(DiscordRPCError::Timeout));
+
+        let result = self.connection.close().await;
+        self.connection.mark_closed("closed by the client");
+        self.worker.abort();
+
+        result.and(cleared.map(drop))
+    }
+
+    /// Calls `func` for every event and command response that was not
+    /// awaited through [`request`], until the returned handle is dropped
+    /// or the client is closed.
     ///
-    /// This method sends the handshake signal to the IPC.
-    /// It is usually not called manually, as it is automatically
-    /// called by [`connect`] and/or [`reconnect`].
+    /// Failures nobody awaits are passed to `func` as well, such as frames
+    /// that could not be decoded, error responses to emitted commands and
+    /// the error that ended a connection. The client reconnects by itself,
+    /// see [`connection_state`] for how that goes.
     ///
-    /// [`connect`]: #method.connect
-    /// [`reconnect`]: #method.reconnect
+    /// [`request`]: #method.request
+    /// [`connection_state`]: #method.connection_state
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let _handler = client.handler(|event| match event {
+    ///     Ok(event) => println!("{:?}", event),
+    ///     Err(e) => println!("discord: {}", e),
+    /// });
+    /// ```
+    pub fn handler<F>(&self, mut func: F) -> HandlerHandle
+    where
+        F: FnMut(std::result::Result<EventReceive, Arc<DiscordRPCError>>) + Send + 'static,
+    {
+        let mut events = self.connection.events.subscribe();
+        let mut errors = self.connection.errors.subscribe();
+        let mut closed = self.connection.closed.subscribe();
+        let task = tokio::spawn(async move {
+            loop {
+                // hand out what was received before noticing the close
+                tokio::select! {
+                    biased;
+                    payload = events.recv() => match payload {
+                        Ok(payload) => func(
+                            serde_json::from_str::<EventReceive>(&payload)
+                                .map_err(|e| Arc::new(e.into())),
+                        ),
+                        // frames missed by a slow handler are gone, go on with the newer ones
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    error = errors.recv() => match error {
+                        Ok(error) => func(Err(error)),
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    // the flag is only ever set, so any change means closed
+                    _ = closed.changed() => break,
+                }
+            }
+        });
+        HandlerHandle::new(task)
+    }
+}
+
+impl Drop for DiscordIPCClient {
+    fn drop(&mut self) {
+        self.connection.mark_closed("the client was dropped");
+        self.worker.abort();
+    }
+}
+
+impl DiscordIPCClientBuilder {
+    /// Sets the backoff used between reconnect attempts.
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Connects to this socket instead of searching for one.
+    pub fn socket_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.socket_path = Some(path.into());
+        self
+    }
+
+    /// Sets the largest payload sent or accepted, in bytes. Frames above it
+    /// fail with [`DiscordRPCError::FrameTooLarge`].
+    pub fn max_payload(mut self, max_payload: usize) -> Self {
+        self.max_payload = max_payload;
+        self
+    }
+
+    /// Sets how long a request waits for its response before failing with
+    /// [`DiscordRPCError::Timeout`]. `AUTHORIZE` is exempt, as it waits for
+    /// the user to answer Discord's consent modal.
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// Connects the client to the Discord IPC.
     ///
     /// # Errors
     ///
-    /// Returns an `Err` variant if sending the handshake failed.
-    async fn send_handshake(&mut self) -> Result<()> {
-        self.socket
-            .send(
-                &json!({
-                  "v": 1,
-                  "client_id": self.client_id
+    /// Returns an `Err` variant if no socket could be found or
+    /// the connection could not be established.
+    pub async fn connect(self) -> Result<DiscordIPCClient> {
+        let mut socket = DiscordIPCSocket::new(self.socket_path).await?;
+        let mut session = Session::with_max_payload(&self.client_id, self.max_payload);
+        let ready = socket.handshake(&mut session).await?;
+
+        let (messages, receiver) = mpsc::unbounded_channel();
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        let (errors, _) = broadcast::channel(EVENT_CAPACITY);
+        let connection = Connection {
+            messages,
+            request_timeout: self.request_timeout,
+            events,
+            errors,
+            subscriptions: Default::default(),
+            closed: Arc::new(watch::channel(false).0),
+            state: Arc::new(
+                watch::channel(ConnectionState::Ready {
+                    user: ready.user.clone(),
                 })
-                .to_string(),
-                OPCODES::Handshake as u8,
-            )
-            .await?;
+                .0,
+            ),
+            ready: Arc::new(StdMutex::new(ready)),
+        };
 
-        // // TODO: Return an Err if the handshake is rejected
-        // NOTE: this prolly shouldnt be done here as we dont want to consume messages here
-        // self.recv()?;
+        let worker = Worker {
+            socket,
+            session,
+            backoff: self.backoff,
+            messages: receiver,
+            connection: connection.clone(),
+            pending: HashMap::new(),
+            last_activity: None,
+        };
 
-        Ok(())
+        Ok(DiscordIPCClient {
+            client_id: self.client_id,
+            connection,
+            worker: tokio::spawn(worker.run()),
+        })
     }
+}
 
-    /// send a json string payload to the socket
-    pub async fn emit_string(&mut self, payload: String) -> Result<()> {
-        self.socket
-            .send(&payload, OPCODES::Frame as u8)
+impl Connection {
+    fn set_ready(&self, ready: ReadyData) {
+        let user = ready.user.clone();
+        *self.ready.lock().unwrap() = ready;
+        self.state.send_replace(ConnectionState::Ready { user });
+    }
+
+    fn ready(&self) -> ReadyData {
+        self.ready.lock().unwrap().clone()
+    }
+
+    /// Hands a message to the worker, returning the receiver of its outcome.
+    fn send<T, F>(&self, message: F) -> Result<oneshot::Receiver<Result<T>>>
+    where
+        F: FnOnce(oneshot::Sender<Result<T>>) -> Message,
+    {
+        if self.is_closed() {
+            return Err(DiscordRPCError::ClientClosed);
+        }
+
+        let (sender, receiver) = oneshot::channel();
+        self.messages
+            .send(message(sender))
+            .map_err(|_| DiscordRPCError::ClientClosed)?;
+        Ok(receiver)
+    }
+
+    /// Hands a message to the worker and waits for its outcome.
+    async fn call<T, F>(&self, message: F) -> Result<T>
+    where
+        F: FnOnce(oneshot::Sender<Result<T>>) -> Message,
+    {
+        self.send(message)?
             .await
-            .unwrap();
-        Ok(())
+            .unwrap_or(Err(DiscordRPCError::ConnectionLost))
     }
 
-    /// send a json string payload to the socket
-    pub async fn emit_command(&mut self, command: &RPCCommand) -> Result<()> {
-        let mut command_json = command.to_json()?;
-        //println!("{}", command_json);
-        let json_string = &create_json(&mut command_json)?;
-        self.socket
-            .send(json_string, OPCODES::Frame as u8)
+    pub(crate) async fn request(&self, command: &RPCCommand) -> Result<EventFunctionPayload> {
+        self.queue_request(command)?.await
+    }
+
+    /// Hands `command` to the worker right away and returns the wait for
+    /// its response. Commands queued one after another are sent in that
+    /// order, even if their responses are awaited elsewhere.
+    pub(crate) fn queue_request(
+        &self,
+        command: &RPCCommand,
+    ) -> Result<impl Future<Output = Result<EventFunctionPayload>>> {
+        let receiver = self.send(|reply| Message::Request {
+            command: command.clone(),
+            reply,
+        })?;
+        // the user may take a while to answer the consent modal
+        let timeout = match command {
+            RPCCommand::Authorize(_) => None,
+            _ => Some(self.request_timeout),
+        };
+
+        Ok(async move {
+            let response = async {
+                receiver
+                    .await
+                    .unwrap_or(Err(DiscordRPCError::ConnectionLost))
+            };
+            match timeout {
+                Some(timeout) => tokio::time::timeout(timeout, response)
+                    .await
+                    .unwrap_or(Err(DiscordRPCError::Timeout)),
+                None => response.await,
+            }
+        })
+    }
+
+    async fn emit(&self, command: &RPCCommand) -> Result<()> {
+        self.call(|sent| Message::Emit {
+            command: command.clone(),
+            sent,
+        })
+        .await
+    }
+
+    async fn emit_payload(&self, payload: String) -> Result<()> {
+        self.call(|sent| Message::EmitPayload { payload, sent })
             .await
-            .unwrap();
-        Ok(())
     }
 
-    pub async fn handler<F>(&mut self, func: F)
+    async fn reconnect(&self, reason: &str) -> Result<()> {
+        self.call(|done| Message::Reconnect {
+            reason: reason.to_string(),
+            done,
+        })
+        .await
+    }
+
+    /// Sends the `Close` frame, giving up after [`CLOSE_TIMEOUT`] if the
+    /// worker is busy reconnecting.
+    async fn close(&self) -> Result<()> {
+        match tokio::time::timeout(CLOSE_TIMEOUT, self.call(|done| Message::Close { done })).await {
+            Ok(result) => result,
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Drops one stream's hold on its subscription. When it was the last
+    /// one, `UNSUBSCRIBE` is queued and the wait for its response returned.
+    ///
+    /// The registry stays locked until the command is queued, so it goes
+    /// out in the same order as a `SUBSCRIBE` for the same event.
+    pub(crate) fn release(
+        &self,
+        token: &SubscriptionToken,
+    ) -> Result<Option<impl Future<Output = Result<EventFunctionPayload>>>> {
+        if self.is_closed() {
+            return Ok(None);
+        }
+
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        if !subscriptions.release(token) {
+            return Ok(None);
+        }
+        self.queue_request(&RPCCommand::Unsubscribe(token.event.clone()))
+            .map(Some)
+    }
+
+    /// Passes an error nobody awaits on to the [`DiscordIPCClient::handler`]s.
+    pub(crate) fn report(&self, error: DiscordRPCError) {
+        // nobody may be listening, which is fine
+        let _ = self.errors.send(Arc::new(error));
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        *self.closed.borrow()
+    }
+
+    /// Stops every background task for good, keeping the first reason.
+    fn mark_closed(&self, reason: &str) {
+        if self.closed.send_replace(true) {
+            return;
+        }
+        self.state.send_replace(ConnectionState::Closed {
+            reason: reason.to_string(),
+        });
+    }
+}
+
+impl Worker {
+    /// Reads frames and handles messages until the client is closed,
+    /// reconnecting whenever the socket goes away. Stops for good once
+    /// the reconnect gave up.
+    async fn run(mut self) {
+        let mut buf = [0u8; READ_BUFFER_SIZE];
+        loop {
+            if let Err(e) = self.drain().await {
+                self.recover(e).await;
+            }
+            if self.connection.is_closed() {
+                return;
+            }
+
+            tokio::select! {
+                read = self.socket.read(&mut buf) => match read {
+                    Ok(0) => {
+                        let e = self.session.finish().err().unwrap_or_else(|| {
+                            io::Error::from(io::ErrorKind::UnexpectedEof).into()
+                        });
+                        self.recover(e).await;
+                    }
+                    Ok(read) => self.session.receive(&buf[..read]),
+                    Err(e) => self.recover(e).await,
+                },
+                message = self.messages.recv() => match message {
+                    Some(Message::Close { done }) => {
+                        let _ = done.send(self.close().await);
+                        return;
+                    }
+                    Some(message) => self.handle(message).await,
+                    None => return,
+                },
+            }
+        }
+    }
+
+    /// Dispatches every frame decoded so far and writes the pongs the
+    /// session queued meanwhile.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error that ended the session, frames that could merely
+    /// not be decoded are reported to the handlers and skipped.
+    async fn drain(&mut self) -> Result<()> {
+        loop {
+            match self.session.poll() {
+                Ok(Some(event)) => self.dispatch(event),
+                Ok(None) => break,
+                Err(e) if self.session.state() == SessionState::Closed => return Err(e),
+                Err(e) => self.report(e),
+            }
+        }
+        self.socket.flush(&mut self.session).await
+    }
+
+    /// Hands a response to the request waiting for its nonce, and anything
+    /// else to the event subscribers.
+    fn dispatch(&mut self, event: SessionEvent) {
+        match event {
+            SessionEvent::Response { nonce, result } => match self.pending.remove(&nonce) {
+                Some(sender) => {
+                    let _ = sender.send(result);
+                }
+                // emitted and replayed commands, the handlers get their responses
+                None => match result.and_then(|payload| Ok(serde_json::to_string(&payload)?)) {
+                    Ok(payload) => {
+                        let _ = self.connection.events.send(payload);
+                    }
+                    Err(e) => self.report(e),
+                },
+            },
+            SessionEvent::Event(payload) => {
+                // nobody may be listening, which is fine
+                let _ = self.connection.events.send(payload);
+            }
+            // only sent in reply to a handshake, which is awaited where it is sent
+            SessionEvent::Ready(_) => {}
+        }
+    }
+
+    fn report(&self, error: DiscordRPCError) {
+        self.connection.report(error);
+    }
+
+    async fn handle(&mut self, message: Message) {
+        match message {
+            Message::Request { command, reply } => match self.send_command(&command).await {
+                Ok(nonce) => {
+                    // forget requests whose caller stopped waiting, e.g. after a timeout
+                    self.pending.retain(|_, reply| !reply.is_closed());
+                    self.pending.insert(nonce, reply);
+                }
+                Err(e) => {
+                    let _ = reply.send(Err(e));
+                }
+            },
+            Message::Emit { command, sent } => {
+                let _ = sent.send(self.send_command(&command).await.map(drop));
+            }
+            Message::EmitPayload { payload, sent } => {
+                let result = match self.session.send_payload(&payload) {
+                    Ok(()) => self.flush(|session| session.send_payload(&payload)).await,
+                    Err(e) => Err(e),
+                };
+                let _ = sent.send(result);
+            }
+            Message::Reconnect { reason, done } => {
+                let _ = done.send(self.reconnect(reason).await.map(drop));
+            }
+            Message::Close { done } => {
+                let _ = done.send(self.close().await);
+            }
+        }
+    }
+
+    /// Sends `command` and returns the nonce its response will carry.
+    ///
+    /// If the write reveals a dropped socket, the command is sent again on
+    /// the new connection, so the nonce returned is the one of that send.
+    /// Activities are replayed by the reconnect itself and not sent twice.
+    async fn send_command(&mut self, command: &RPCCommand) -> Result<String> {
+        let nonce = self.session.send_command(command)?;
+        let is_activity = matches!(command, RPCCommand::SetActivity(_));
+        if is_activity {
+            self.last_activity = Some(command.clone());
+        }
+
+        match self.socket.flush(&mut self.session).await {
+            Err(e @ DiscordRPCError::Io(_)) => {
+                let replayed = self.reconnect_after(e).await?;
+                let nonce = match replayed {
+                    Some(nonce) if is_activity => nonce,
+                    _ => self.session.send_command(command)?,
+                };
+                self.socket.flush(&mut self.session).await?;
+                Ok(nonce)
+            }
+            Err(e) => Err(e),
+            Ok(()) => Ok(nonce),
+        }
+    }
+
+    /// Writes what the session queued, reconnecting once if the socket
+    /// turns out to be gone. `resend` queues the frame again on the new
+    /// connection.
+    async fn flush<F>(&mut self, resend: F) -> Result<()>
     where
-        F: Fn(EventReceive) + Send + Sync + 'static,
+        F: FnOnce(&mut Session) -> Result<()>,
     {
-        let mut socket_clone = self.socket.clone();
-        tokio::spawn(async move {
-            loop {
-                let (_opcode, payload) = socket_clone.recv().await.unwrap();
+        match self.socket.flush(&mut self.session).await {
+            Err(e @ DiscordRPCError::Io(_)) => {
+                self.reconnect_after(e).await?;
+                resend(&mut self.session)?;
+                self.socket.flush(&mut self.session).await
+            }
+            result => result,
+        }
+    }
 
-                //println!("{}", &payload);
-                match serde_json::from_str::<EventReceive>(&payload) {
-                    Ok(e) => {
-                        func(e);
-                    }
-                    Err(e) => {
-                        println!("{:#?}", e);
+    /// Reports `error`, which ended the connection, to the handlers and
+    /// reconnects.
+    async fn reconnect_after(&mut self, error: DiscordRPCError) -> Result<Option<String>> {
+        let reason = error.to_string();
+        self.report(error);
+        self.reconnect(reason).await
+    }
+
+    /// Like [`reconnect_after`](Self::reconnect_after), for errors nobody
+    /// is waiting on.
+    async fn recover(&mut self, error: DiscordRPCError) {
+        if let Err(e) = self.reconnect_after(error).await {
+            // giving up already closed the client and says why in its state
+            if !self.connection.is_closed() {
+                self.report(e);
+            }
+        }
+    }
+
+    /// Re-runs socket discovery and the handshake with exponential backoff,
+    /// then replays the last activity and the subscriptions.
+    ///
+    /// `reason` is published in the [`ConnectionState`]. Returns the nonce
+    /// the activity was replayed with, if there is one.
+    async fn reconnect(&mut self, reason: String) -> Result<Option<String>> {
+        self.connection
+            .state
+            .send_replace(ConnectionState::Reconnecting {
+                reason,
+                attempts: 0,
+            });
+
+        // responses to requests sent on the old socket will never arrive
+        self.fail_pending();
+
+        let mut delays = self.backoff.delays();
+        loop {
+            match self.handshake().await {
+                Ok(ready) => {
+                    self.connection.set_ready(ready);
+                    break;
+                }
+                Err(e) => {
+                    self.connection.state.send_modify(|state| {
+                        let attempts = match state {
+                            ConnectionState::Reconnecting { attempts, .. } => *attempts + 1,
+                            _ => 1,
+                        };
+                        *state = ConnectionState::Reconnecting {
+                            reason: e.to_string(),
+                            attempts,
+                        };
+                    });
+                    match delays.next() {
+                        Some(delay) => tokio::time::sleep(delay).await,
+                        None => {
+                            self.give_up(&e);
+                            return Err(e);
+                        }
                     }
                 }
             }
-        });
+        }
+
+        let replayed = match &self.last_activity {
+            Some(activity) => Some(self.session.send_command(activity)?),
+            None => None,
+        };
+
+        // the new connection knows nothing about the old subscriptions,
+        // their responses end up with the event subscribers
+        let subscriptions = self.connection.subscriptions.lock().unwrap().events();
+        for event in subscriptions {
+            self.session.send_command(&RPCCommand::Subscribe(event))?;
+        }
+
+        self.socket.flush(&mut self.session).await?;
+        Ok(replayed)
+    }
+
+    /// Closes the client after the last reconnect attempt failed with `error`.
+    fn give_up(&mut self, error: &DiscordRPCError) {
+        self.connection
+            .mark_closed(&format!("gave up reconnecting: {}", error));
+        self.fail_pending();
+    }
+
+    async fn handshake(&mut self) -> Result<ReadyData> {
+        self.socket.reconnect().await?;
+        self.socket.handshake(&mut self.session).await
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        self.fail_pending();
+        self.socket.close(&mut self.session).await
+    }
+
+    fn fail_pending(&mut self) {
+        for (_nonce, sender) in self.pending.drain() {
+            let _ = sender.send(Err(DiscordRPCError::ConnectionLost));
+        }
     }
 }
@@ -1,15 +1,13 @@
 use crate::Result;
-use std::sync::Arc;
+use std::io;
+use std::path::{Path, PathBuf};
 
 #[cfg(target_family = "unix")]
 use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
 
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 #[cfg(target_family = "unix")]
 use tokio::net::UnixStream;
-use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt},
-    sync::Mutex,
-};
 
 #[cfg(target_family = "windows")]
 use tokio::{
@@ -17,7 +15,12 @@ use tokio::{
     net::windows::named_pipe::{ClientOptions, NamedPipeClient},
 };
 
-use crate::{errors::DiscordRPCError, get_pipe_pattern, pack, unpack};
+use crate::{
+    errors::DiscordRPCError,
+    find_pipe,
+    models::events::ReadyData,
+    protocol::{Session, SessionEvent},
+};
 
 #[cfg(target_family = "windows")]
 type ReadHalfType = ReadHalf<NamedPipeClient>;
@@ -29,17 +32,24 @@ type ReadHalfType = OwnedReadHalf;
 #[cfg(target_family = "unix")]
 type WriteHalfType = OwnedWriteHalf;
 
-#[derive(Clone)]
+/// Size of the chunks read from the socket.
+pub(crate) const READ_BUFFER_SIZE: usize = 4096;
+
+/// The byte stream to a Discord client. Framing, handshakes and pings are
+/// left to the [`Session`] driving it.
 pub(crate) struct DiscordIPCSocket {
-    read_half: Arc<Mutex<ReadHalfType>>,
-    write_half: Arc<Mutex<WriteHalfType>>,
+    path_override: Option<PathBuf>,
+    read_half: ReadHalfType,
+    write_half: WriteHalfType,
 }
 
 impl DiscordIPCSocket {
     /// Used to get the a socket like impl on windows as technical it's a named pipe
     #[cfg(target_os = "windows")]
-    async fn get_inner_socket() -> Result<(ReadHalfType, WriteHalfType)> {
-        let path = get_pipe_pattern();
+    async fn get_inner_socket(
+        path_override: Option<&Path>,
+    ) -> Result<(ReadHalfType, WriteHalfType)> {
+        let path = find_pipe(path_override)?;
         if let Ok(client) = ClientOptions::new().open(&path) {
             let (read_half, write_half) = tokio::io::split(client);
             return Ok((read_half, write_half));
@@ -49,8 +59,10 @@ impl DiscordIPCSocket {
     }
 
     #[cfg(target_family = "unix")]
-    async fn get_inner_socket() -> Result<(ReadHalfType, WriteHalfType)> {
-        let path = get_pipe_pattern();
+    async fn get_inner_socket(
+        path_override: Option<&Path>,
+    ) -> Result<(ReadHalfType, WriteHalfType)> {
+        let path = find_pipe(path_override)?;
 
         if let Ok(socket) = UnixStream::connect(&path).await {
             return Ok(socket.into_split());
@@ -59,46 +71,88 @@ impl DiscordIPCSocket {
         Err(DiscordRPCError::CouldNotConnect)
     }
 
-    pub(crate) async fn new() -> Result<Self> {
-        let (read_half, write_half) = Self::get_inner_socket().await?;
+    /// Connects to the first Discord IPC socket found, or to `path_override`
+    /// when one is given.
+    pub(crate) async fn new(path_override: Option<PathBuf>) -> Result<Self> {
+        let (read_half, write_half) = Self::get_inner_socket(path_override.as_deref()).await?;
         Ok(Self {
-            read_half: Arc::new(Mutex::new(read_half)),
-            write_half: Arc::new(Mutex::new(write_half)),
+            path_override,
+            read_half,
+            write_half,
         })
     }
 
+    /// Reads whatever is available, returning 0 once the peer hung up.
+    pub(crate) async fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        Ok(self.read_half.read(buf).await?)
+    }
+
     pub(crate) async fn write(&mut self, buf: &[u8]) -> Result<()> {
-        let mut socket = self.write_half.lock().await;
-        socket.write_all(buf).await?;
+        self.write_half.write_all(buf).await?;
         Ok(())
     }
 
-    pub(crate) async fn read(&mut self, buf: &mut [u8]) -> Result<()> {
-        let mut socket = self.read_half.lock().await;
-        socket.read_exact(buf).await?;
+    /// Writes everything `session` queued.
+    pub(crate) async fn flush(&mut self, session: &mut Session) -> Result<()> {
+        let outgoing = session.take_outgoing();
+        if !outgoing.is_empty() {
+            self.write(&outgoing).await?;
+        }
         Ok(())
     }
 
-    pub(crate) async fn send(&mut self, data: &str, opcode: u8) -> Result<()> {
-        let mut packet = pack(opcode.into(), data.len() as u32)?;
-
-        packet.extend(data.as_bytes());
+    /// Reads until `session` yields an event.
+    pub(crate) async fn next_event(&mut self, session: &mut Session) -> Result<SessionEvent> {
+        let mut buf = [0u8; READ_BUFFER_SIZE];
+        loop {
+            let event = session.poll();
+            // pongs are queued while polling
+            self.flush(session).await?;
+            if let Some(event) = event? {
+                return Ok(event);
+            }
+
+            let read = self.read(&mut buf).await?;
+            if read == 0 {
+                session.finish()?;
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+            session.receive(&buf[..read]);
+        }
+    }
 
-        self.write(&packet).await?;
+    /// Starts a new `session` on this socket and waits for `READY`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiscordRPCError::HandshakeFailed`] if Discord rejected the
+    /// handshake, or an `Err` variant if the socket failed.
+    pub(crate) async fn handshake(&mut self, session: &mut Session) -> Result<ReadyData> {
+        session.handshake();
+        self.flush(session).await?;
+
+        loop {
+            if let SessionEvent::Ready(ready) = self.next_event(session).await? {
+                return Ok(ready);
+            }
+        }
+    }
 
+    /// Ends `session` with a `Close` frame and shuts the connection down
+    /// for writing.
+    pub(crate) async fn close(&mut self, session: &mut Session) -> Result<()> {
+        session.close();
+        self.flush(session).await?;
+        self.write_half.shutdown().await?;
         Ok(())
     }
 
-    pub(crate) async fn recv(&mut self) -> Result<(u32, String)> {
-        let mut header = [0u8; 8];
-        self.read(&mut header).await?;
-        let (op, length) = unpack(header.to_vec())?;
-
-        let mut data = vec![0u8; length as usize];
-        self.read(&mut data).await?;
-
-        let response = String::from_utf8(data)?;
-
-        Ok((op, response))
+    /// Replaces the underlying connection with a fresh one, looking the
+    /// socket up again unless a path was given.
+    pub(crate) async fn reconnect(&mut self) -> Result<()> {
+        let (read_half, write_half) = Self::get_inner_socket(self.path_override.as_deref()).await?;
+        self.read_half = read_half;
+        self.write_half = write_half;
+        Ok(())
     }
 }
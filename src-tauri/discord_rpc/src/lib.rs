@@ -1,19 +1,45 @@
 pub mod errors;
 pub mod models;
 pub mod opcodes;
+pub mod protocol;
 pub mod utils;
 
+#[cfg(all(unix, feature = "blocking"))]
+pub mod blocking;
+#[cfg(feature = "tokio")]
+pub mod discovery;
+#[cfg(all(unix, feature = "test-support"))]
+pub mod testing;
+
+mod backoff;
+#[cfg(feature = "tokio")]
+mod connection_state;
+#[cfg(feature = "tokio")]
 mod ipc;
+#[cfg(feature = "tokio")]
 mod ipc_socket;
+#[cfg(feature = "tokio")]
+mod presence_queue;
+#[cfg(feature = "tokio")]
+mod subscription;
 
+pub use backoff::Backoff;
+#[cfg(feature = "tokio")]
+pub use connection_state::ConnectionState;
 use errors::DiscordRPCError;
-pub use ipc::DiscordIPCClient;
+#[cfg(feature = "tokio")]
+pub use ipc::{DiscordIPCClient, DiscordIPCClientBuilder};
 use models::{commands::EventFunctionPayload, events::EventPayload};
+#[cfg(feature = "tokio")]
+pub use presence_queue::{Delivery, PresenceQueue, PresenceReport, RateLimit};
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "tokio")]
+pub use subscription::{EventStream, HandlerHandle};
 pub use utils::*;
 
 pub type Result<T, E = DiscordRPCError> = std::result::Result<T, E>;
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(untagged)]
 pub enum EventReceive {
@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+
+/// OAuth2 scopes that can be requested through `AUTHORIZE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum OAuthScope {
+    #[serde(rename = "identify")]
+    Identify,
+    #[serde(rename = "email")]
+    Email,
+    #[serde(rename = "connections")]
+    Connections,
+    #[serde(rename = "guilds")]
+    Guilds,
+    #[serde(rename = "guilds.join")]
+    GuildsJoin,
+    #[serde(rename = "guilds.members.read")]
+    GuildsMembersRead,
+    #[serde(rename = "messages.read")]
+    MessagesRead,
+    #[serde(rename = "rpc")]
+    Rpc,
+    #[serde(rename = "rpc.activities.write")]
+    RpcActivitiesWrite,
+    #[serde(rename = "rpc.notifications.read")]
+    RpcNotificationsRead,
+    #[serde(rename = "rpc.voice.read")]
+    RpcVoiceRead,
+    #[serde(rename = "rpc.voice.write")]
+    RpcVoiceWrite,
+    #[serde(rename = "activities.read")]
+    ActivitiesRead,
+    #[serde(rename = "activities.write")]
+    ActivitiesWrite,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct AuthorizeArgs {
+    pub client_id: String,
+    pub scopes: Vec<OAuthScope>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rpc_token: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+}
+
+impl AuthorizeArgs {
+    pub fn new(client_id: &str, scopes: Vec<OAuthScope>) -> Self {
+        Self {
+            client_id: client_id.to_string(),
+            scopes,
+            rpc_token: None,
+            username: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct AuthenticateArgs {
+    pub access_token: String,
+}
+
+impl AuthenticateArgs {
+    pub fn new(access_token: &str) -> Self {
+        Self {
+            access_token: access_token.to_string(),
+        }
+    }
+}
+
+/// Response to `AUTHORIZE`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AuthorizeData {
+    pub code: String,
+}
+
+/// Body of the request that exchanges an authorization code for a token
+/// at Discord's `/oauth2/token` endpoint.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct OAuthRequest {
+    pub client_id: String,
+    pub code: String,
+    pub grant_type: String,
+}
+
+/// Response of the `/oauth2/token` endpoint.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: u64,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    pub scope: String,
+}
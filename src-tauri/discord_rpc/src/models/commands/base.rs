@@ -1,28 +1,67 @@
 use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-use super::ChannelData;
+use crate::models::events::AuthData;
+
+use super::{
+    AuthorizeData, ChannelData, ChannelsData, GuildData, GuildsData, ImageData, VoiceSettings,
+};
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(tag = "cmd")]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum EventFunctionPayload {
-    GetSelectedVoiceChannel {
-        data: Option<ChannelData>,
-    },
+    /// Authorize the application
+    Authorize { data: AuthorizeData },
+
+    /// Authenticate with an access token
+    Authenticate { data: AuthData },
+
+    /// Get a guild
+    GetGuild { data: GuildData },
+
+    /// Get the guilds the user is in
+    GetGuilds { data: GuildsData },
+
+    /// Get a channel
+    GetChannel { data: ChannelData },
+
+    /// Get the channels of a guild
+    GetChannels { data: ChannelsData },
 
     /// Get the selected voice channel
-    SelectVoiceChannel {
-        data: ChannelData,
-    },
+    GetSelectedVoiceChannel { data: Option<ChannelData> },
+
+    /// Join or leave a voice channel
+    SelectVoiceChannel { data: Option<ChannelData> },
+
+    /// Open or leave a text channel
+    SelectTextChannel { data: Option<ChannelData> },
+
+    /// Get the user's voice settings
+    GetVoiceSettings { data: VoiceSettings },
+
+    /// Change the user's voice settings
+    SetVoiceSettings { data: VoiceSettings },
+
+    /// Get an image as a data uri
+    GetImage { data: ImageData },
+
+    /// Set the activity
+    SetActivity { data: Option<Value> },
+
+    /// Accept an activity join request
+    SendActivityJoinInvite { data: Option<Value> },
+
+    /// Reject an activity join request
+    CloseActivityJoinRequest { data: Option<Value> },
 
     /// Subscribe
-    Subscribe {
-        data: HashMap<String, String>,
-    },
+    Subscribe { data: HashMap<String, String> },
+    /// Unsubscribe
+    Unsubscribe { data: HashMap<String, String> },
     /// Dispatch
-    Dispatch {
-        data: HashMap<String, String>,
-    },
+    Dispatch { data: HashMap<String, String> },
 }
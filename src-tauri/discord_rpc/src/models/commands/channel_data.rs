@@ -2,17 +2,121 @@ use serde::{Deserialize, Serialize};
 
 use crate::models::shared::VoiceState;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ChannelData {
     pub id: String,
     pub name: String,
     /// This is the "type" field that comes from the discord api
     #[serde(rename = "type")]
     pub event_type: u32,
+    #[serde(default)]
     pub topic: String,
+    #[serde(default)]
     pub bitrate: u32,
+    #[serde(default)]
     pub user_limit: u32,
-    pub guild_id: String,
+    pub guild_id: Option<String>,
+    #[serde(default)]
     pub position: u32,
+    #[serde(default)]
     pub voice_states: Vec<VoiceState>,
 }
+
+/// A channel as listed by `GET_CHANNELS`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PartialChannel {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub channel_type: u32,
+}
+
+/// Response to `GET_CHANNELS`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChannelsData {
+    pub channels: Vec<PartialChannel>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct GetChannelArgs {
+    pub channel_id: String,
+}
+
+impl GetChannelArgs {
+    pub fn new(channel_id: &str) -> Self {
+        Self {
+            channel_id: channel_id.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct GetChannelsArgs {
+    pub guild_id: String,
+}
+
+impl GetChannelsArgs {
+    pub fn new(guild_id: &str) -> Self {
+        Self {
+            guild_id: guild_id.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SelectVoiceChannelArgs {
+    /// The channel to join, `None` leaves the current one
+    pub channel_id: Option<String>,
+
+    /// How long to wait for the channel to become available, in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u32>,
+
+    /// Moves the user even if they are already in another voice channel
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub force: Option<bool>,
+}
+
+impl SelectVoiceChannelArgs {
+    pub fn new(channel_id: Option<&str>) -> Self {
+        Self {
+            channel_id: channel_id.map(String::from),
+            timeout: None,
+            force: None,
+        }
+    }
+
+    pub fn timeout(mut self, timeout: u32) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn force(mut self, force: bool) -> Self {
+        self.force = Some(force);
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SelectTextChannelArgs {
+    /// The channel to open, `None` leaves the current one
+    pub channel_id: Option<String>,
+
+    /// How long to wait for the channel to become available, in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u32>,
+}
+
+impl SelectTextChannelArgs {
+    pub fn new(channel_id: Option<&str>) -> Self {
+        Self {
+            channel_id: channel_id.map(String::from),
+            timeout: None,
+        }
+    }
+
+    pub fn timeout(mut self, timeout: u32) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
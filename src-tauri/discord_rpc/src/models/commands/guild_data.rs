@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct GetGuildArgs {
+    pub guild_id: String,
+
+    /// How long to wait for the guild to become available, in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u32>,
+}
+
+impl GetGuildArgs {
+    pub fn new(guild_id: &str) -> Self {
+        Self {
+            guild_id: guild_id.to_string(),
+            timeout: None,
+        }
+    }
+
+    pub fn timeout(mut self, timeout: u32) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+/// Response to `GET_GUILD`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GuildData {
+    pub id: String,
+    pub name: String,
+    pub icon_url: Option<String>,
+    /// Always empty, kept for compatibility with the api
+    #[serde(default)]
+    pub members: Vec<Value>,
+    #[serde(default)]
+    pub vanity_url_code: Option<String>,
+}
+
+/// A guild as listed by `GET_GUILDS`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PartialGuild {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub icon_url: Option<String>,
+}
+
+/// Response to `GET_GUILDS`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GuildsData {
+    pub guilds: Vec<PartialGuild>,
+}
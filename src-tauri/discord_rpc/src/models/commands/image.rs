@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+/// Kind of image `GET_IMAGE` can fetch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageType {
+    /// A user's avatar, `id` is the user id
+    User,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    Png,
+    Webp,
+    Jpg,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct GetImageArgs {
+    #[serde(rename = "type")]
+    pub image_type: ImageType,
+    pub id: String,
+    pub format: ImageFormat,
+    /// Width and height in pixels, a power of two between 16 and 1024
+    pub size: u32,
+}
+
+impl GetImageArgs {
+    /// Fetches the avatar of `user_id` as a 128px png.
+    pub fn user(user_id: &str) -> Self {
+        Self {
+            image_type: ImageType::User,
+            id: user_id.to_string(),
+            format: ImageFormat::Png,
+            size: 128,
+        }
+    }
+
+    pub fn format(mut self, format: ImageFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn size(mut self, size: u32) -> Self {
+        self.size = size;
+        self
+    }
+}
+
+/// Response to `GET_IMAGE`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ImageData {
+    /// The image as a `data:` uri, usable as an `img` source
+    pub data_uri: String,
+}
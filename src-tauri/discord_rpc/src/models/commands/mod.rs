@@ -1,10 +1,18 @@
+mod authorize;
 mod base;
 mod channel_data;
+mod guild_data;
+mod image;
 mod presence;
 mod speaking_data;
+mod voice_settings;
 
+pub use authorize::*;
 pub use base::*;
 
 pub use channel_data::*;
+pub use guild_data::*;
+pub use image::*;
 pub use presence::*;
 pub use speaking_data::*;
+pub use voice_settings::*;
@@ -1,12 +1,15 @@
 use std::default::Default;
+use std::ops::RangeInclusive;
 
 use serde::{Deserialize, Serialize};
 
+use crate::errors::{DiscordRPCError, InvalidField};
 use crate::pub_struct;
+use crate::Result;
 
 use super::super::super::utils;
 
-#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct SetActivityArgs {
     pub pid: u32,
 
@@ -20,15 +23,19 @@ pub_struct!(ActivityTimestamps {
 });
 
 pub_struct!(ActivityParty {
-    id: u32,
+    id: String,
     size: (u32, u32),
 });
 
 pub_struct!(ActivityAssets {
     large_image: String,
     large_text: String,
+    /// Opened when the large image is clicked.
+    large_url: String,
     small_image: String,
     small_text: String,
+    /// Opened when the small image is clicked.
+    small_url: String,
 });
 
 pub_struct!(ActivitySecrets {
@@ -42,13 +49,87 @@ pub_struct!(ActivityButton {
     url: String,
 });
 
+/// What the user is doing, shown as the verb in front of the activity name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(into = "u8", try_from = "u8")]
+pub enum ActivityType {
+    /// "Playing {name}"
+    Playing,
+    /// "Listening to {name}"
+    Listening,
+    /// "Watching {name}"
+    Watching,
+    /// "Competing in {name}"
+    Competing,
+}
+
+impl From<ActivityType> for u8 {
+    fn from(activity_type: ActivityType) -> Self {
+        match activity_type {
+            ActivityType::Playing => 0,
+            ActivityType::Listening => 2,
+            ActivityType::Watching => 3,
+            ActivityType::Competing => 5,
+        }
+    }
+}
+
+impl TryFrom<u8> for ActivityType {
+    type Error = String;
+
+    fn try_from(value: u8) -> std::result::Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ActivityType::Playing),
+            2 => Ok(ActivityType::Listening),
+            3 => Ok(ActivityType::Watching),
+            5 => Ok(ActivityType::Competing),
+            _ => Err(format!("unsupported activity type {}", value)),
+        }
+    }
+}
+
+/// Which field replaces the application name in the member list status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(into = "u8", try_from = "u8")]
+pub enum StatusDisplayType {
+    Name,
+    State,
+    Details,
+}
+
+impl From<StatusDisplayType> for u8 {
+    fn from(display_type: StatusDisplayType) -> Self {
+        display_type as u8
+    }
+}
+
+impl TryFrom<u8> for StatusDisplayType {
+    type Error = String;
+
+    fn try_from(value: u8) -> std::result::Result<Self, Self::Error> {
+        match value {
+            0 => Ok(StatusDisplayType::Name),
+            1 => Ok(StatusDisplayType::State),
+            2 => Ok(StatusDisplayType::Details),
+            _ => Err(format!("unsupported status display type {}", value)),
+        }
+    }
+}
+
 pub_struct!(Activity {
     name: String,
+    #[serde(rename = "type")]
+    activity_type: ActivityType,
+    status_display_type: StatusDisplayType,
     url: String,
     created_at: u64,
     timestamps: ActivityTimestamps,
     details: String,
+    /// Opened when the details line is clicked.
+    details_url: String,
     state: String,
+    /// Opened when the state line is clicked.
+    state_url: String,
     party: ActivityParty,
     assets: ActivityAssets,
     secrets: ActivitySecrets,
@@ -56,6 +137,32 @@ pub_struct!(Activity {
     buttons: Vec<ActivityButton>,
 });
 
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SendActivityJoinInviteArgs {
+    pub user_id: String,
+}
+
+impl SendActivityJoinInviteArgs {
+    pub fn new(user_id: &str) -> Self {
+        Self {
+            user_id: user_id.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CloseActivityJoinRequestArgs {
+    pub user_id: String,
+}
+
+impl CloseActivityJoinRequestArgs {
+    pub fn new(user_id: &str) -> Self {
+        Self {
+            user_id: user_id.to_string(),
+        }
+    }
+}
+
 impl SetActivityArgs {
     pub fn new(f: Activity) -> Self {
         Self {
@@ -73,3 +180,237 @@ impl Default for SetActivityArgs {
         }
     }
 }
+
+/// Length limits Discord enforces on activity strings, in characters.
+const TEXT_LENGTH: RangeInclusive<usize> = 2..=128;
+const IMAGE_KEY_LENGTH: RangeInclusive<usize> = 1..=256;
+const SECRET_LENGTH: RangeInclusive<usize> = 1..=128;
+const BUTTON_LABEL_LENGTH: RangeInclusive<usize> = 1..=32;
+const BUTTON_URL_LENGTH: RangeInclusive<usize> = 1..=512;
+const URL_LENGTH: RangeInclusive<usize> = 1..=256;
+const MAX_BUTTONS: usize = 2;
+
+/// Collects the fields of a model that break Discord's limits.
+#[derive(Default)]
+struct Problems {
+    fields: Vec<InvalidField>,
+}
+
+impl Problems {
+    fn push(&mut self, field: &str, reason: String) {
+        self.fields.push(InvalidField {
+            field: field.to_string(),
+            reason,
+        });
+    }
+
+    /// Checks the length of an optional field, absent ones are fine.
+    fn check_len(
+        &mut self,
+        field: &'static str,
+        value: Option<&str>,
+        range: RangeInclusive<usize>,
+    ) {
+        if let Some(value) = value {
+            let length = value.chars().count();
+            if !range.contains(&length) {
+                self.push(
+                    field,
+                    format!(
+                        "must be {} to {} characters long, got {}",
+                        range.start(),
+                        range.end(),
+                        length
+                    ),
+                );
+            }
+        }
+    }
+
+    fn check_url(
+        &mut self,
+        field: &'static str,
+        value: Option<&str>,
+        range: RangeInclusive<usize>,
+    ) {
+        if let Some(url) = value {
+            self.check_len(field, value, range);
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                self.push(field, "must be an http or https url".to_string());
+            }
+        }
+    }
+
+    /// Takes over the problems a nested model's `validate` found, with
+    /// their field names prefixed by `path`.
+    fn nest(&mut self, path: &str, result: Result<()>) {
+        if let Err(DiscordRPCError::InvalidActivity(fields)) = result {
+            for InvalidField { field, reason } in fields {
+                self.push(&format!("{}.{}", path, field), reason);
+            }
+        }
+    }
+
+    fn into_result(self) -> Result<()> {
+        if self.fields.is_empty() {
+            Ok(())
+        } else {
+            Err(DiscordRPCError::InvalidActivity(self.fields))
+        }
+    }
+}
+
+impl ActivityTimestamps {
+    /// Checks the timestamps against the limits Discord enforces.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiscordRPCError::InvalidActivity`] listing every offending field.
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Problems::default();
+        if let (Some(start), Some(end)) = (self.start, self.end) {
+            if start > end {
+                problems.push("end", "must not be before start".to_string());
+            }
+        }
+        problems.into_result()
+    }
+}
+
+impl ActivityParty {
+    /// Checks the party against the limits Discord enforces.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiscordRPCError::InvalidActivity`] listing every offending field.
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Problems::default();
+        problems.check_len("id", self.id.as_deref(), SECRET_LENGTH);
+        if let Some((current, max)) = self.size {
+            if current == 0 || current > max {
+                problems.push(
+                    "size",
+                    format!(
+                        "must be between 1 and the maximum size, got {} of {}",
+                        current, max
+                    ),
+                );
+            }
+        }
+        problems.into_result()
+    }
+}
+
+impl ActivityAssets {
+    /// Checks the assets against the limits Discord enforces.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiscordRPCError::InvalidActivity`] listing every offending field.
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Problems::default();
+        problems.check_len("large_image", self.large_image.as_deref(), IMAGE_KEY_LENGTH);
+        problems.check_len("large_text", self.large_text.as_deref(), TEXT_LENGTH);
+        problems.check_url("large_url", self.large_url.as_deref(), URL_LENGTH);
+        problems.check_len("small_image", self.small_image.as_deref(), IMAGE_KEY_LENGTH);
+        problems.check_len("small_text", self.small_text.as_deref(), TEXT_LENGTH);
+        problems.check_url("small_url", self.small_url.as_deref(), URL_LENGTH);
+        problems.into_result()
+    }
+}
+
+impl ActivitySecrets {
+    /// Checks the secrets against the limits Discord enforces.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiscordRPCError::InvalidActivity`] listing every offending field.
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Problems::default();
+        problems.check_len("join", self.join.as_deref(), SECRET_LENGTH);
+        problems.check_len("spectate", self.spectate.as_deref(), SECRET_LENGTH);
+        problems.check_len("game", self.game.as_deref(), SECRET_LENGTH);
+        problems.into_result()
+    }
+}
+
+impl ActivityButton {
+    /// Checks the button against the limits Discord enforces.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiscordRPCError::InvalidActivity`] listing every offending field.
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Problems::default();
+        match &self.label {
+            Some(label) => problems.check_len("label", Some(label), BUTTON_LABEL_LENGTH),
+            None => problems.push("label", "is required".to_string()),
+        }
+        match &self.url {
+            Some(url) => problems.check_url("url", Some(url), BUTTON_URL_LENGTH),
+            None => problems.push("url", "is required".to_string()),
+        }
+        problems.into_result()
+    }
+}
+
+impl Activity {
+    /// Checks the activity against the limits Discord enforces.
+    ///
+    /// Discord rejects or silently truncates activities outside of these
+    /// limits, so checking up front gives a useful error instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiscordRPCError::InvalidActivity`] listing every offending field.
+    ///
+    /// # Examples
+    /// ```
+    /// use discord_rpc::models::commands::Activity;
+    ///
+    /// let activity = Activity::new().details("Frieren".to_string()).state("1".to_string());
+    /// assert!(activity.validate().is_err());
+    /// ```
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Problems::default();
+
+        problems.check_len("details", self.details.as_deref(), TEXT_LENGTH);
+        problems.check_url("details_url", self.details_url.as_deref(), URL_LENGTH);
+        problems.check_len("state", self.state.as_deref(), TEXT_LENGTH);
+        problems.check_url("state_url", self.state_url.as_deref(), URL_LENGTH);
+
+        if let Some(buttons) = &self.buttons {
+            if buttons.len() > MAX_BUTTONS {
+                problems.push(
+                    "buttons",
+                    format!(
+                        "must have at most {} buttons, got {}",
+                        MAX_BUTTONS,
+                        buttons.len()
+                    ),
+                );
+            }
+            if self.secrets.is_some() {
+                problems.push("buttons", "cannot be combined with secrets".to_string());
+            }
+        }
+
+        if let Some(timestamps) = &self.timestamps {
+            problems.nest("timestamps", timestamps.validate());
+        }
+        if let Some(party) = &self.party {
+            problems.nest("party", party.validate());
+        }
+        if let Some(assets) = &self.assets {
+            problems.nest("assets", assets.validate());
+        }
+        if let Some(secrets) = &self.secrets {
+            problems.nest("secrets", secrets.validate());
+        }
+        for (i, button) in self.buttons.iter().flatten().enumerate() {
+            problems.nest(&format!("buttons[{}]", i), button.validate());
+        }
+
+        problems.into_result()
+    }
+}
@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+
+use crate::pub_struct;
+
+/// Response to `GET_VOICE_SETTINGS` and `SET_VOICE_SETTINGS`,
+/// also sent with `VOICE_SETTINGS_UPDATE`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VoiceSettings {
+    pub input: VoiceSettingsIo,
+    pub output: VoiceSettingsIo,
+    pub mode: VoiceSettingsMode,
+    pub automatic_gain_control: bool,
+    pub echo_cancellation: bool,
+    pub noise_suppression: bool,
+    pub qos: bool,
+    pub silence_warning: bool,
+    pub deaf: bool,
+    pub mute: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VoiceSettingsIo {
+    pub device_id: String,
+    /// 0 to 100 for input, 0 to 200 for output
+    pub volume: f32,
+    #[serde(default)]
+    pub available_devices: Vec<AvailableDevice>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AvailableDevice {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VoiceSettingsMode {
+    #[serde(rename = "type")]
+    pub mode_type: VoiceModeType,
+    pub auto_threshold: bool,
+    /// Voice activity threshold in dB, from -100 to 0
+    pub threshold: f32,
+    #[serde(default)]
+    pub shortcut: Vec<ShortcutKeyCombo>,
+    /// Push to talk release delay in milliseconds
+    pub delay: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum VoiceModeType {
+    PushToTalk,
+    VoiceActivity,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ShortcutKeyCombo {
+    /// 0 keyboard key, 1 mouse button, 2 keyboard modifier key, 3 gamepad button
+    #[serde(rename = "type")]
+    pub key_type: u32,
+    pub code: u32,
+    pub name: String,
+}
+
+pub_struct!(SetVoiceSettingsIo {
+    device_id: String,
+    volume: f32,
+});
+
+pub_struct!(SetVoiceSettingsMode {
+    #[serde(rename = "type")]
+    mode_type: VoiceModeType,
+    auto_threshold: bool,
+    threshold: f32,
+    shortcut: Vec<ShortcutKeyCombo>,
+    delay: f32,
+});
+
+pub_struct!(SetVoiceSettingsArgs {
+    input: SetVoiceSettingsIo,
+    output: SetVoiceSettingsIo,
+    mode: SetVoiceSettingsMode,
+    automatic_gain_control: bool,
+    echo_cancellation: bool,
+    noise_suppression: bool,
+    qos: bool,
+    silence_warning: bool,
+    deaf: bool,
+    mute: bool,
+});
+
+impl From<&VoiceSettingsIo> for SetVoiceSettingsIo {
+    fn from(io: &VoiceSettingsIo) -> Self {
+        Self::new()
+            .device_id(io.device_id.clone())
+            .volume(io.volume)
+    }
+}
+
+impl From<&VoiceSettingsMode> for SetVoiceSettingsMode {
+    fn from(mode: &VoiceSettingsMode) -> Self {
+        Self::new()
+            .mode_type(mode.mode_type)
+            .auto_threshold(mode.auto_threshold)
+            .threshold(mode.threshold)
+            .shortcut(mode.shortcut.clone())
+            .delay(mode.delay)
+    }
+}
+
+/// Builds the arguments that restore `settings`, e.g. after having
+/// temporarily muted the user.
+impl From<&VoiceSettings> for SetVoiceSettingsArgs {
+    fn from(settings: &VoiceSettings) -> Self {
+        Self::new()
+            .input((&settings.input).into())
+            .output((&settings.output).into())
+            .mode((&settings.mode).into())
+            .automatic_gain_control(settings.automatic_gain_control)
+            .echo_cancellation(settings.echo_cancellation)
+            .noise_suppression(settings.noise_suppression)
+            .qos(settings.qos)
+            .silence_warning(settings.silence_warning)
+            .deaf(settings.deaf)
+            .mute(settings.mute)
+    }
+}
@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::shared::User;
+
+/// Payload of `ACTIVITY_JOIN` and `ACTIVITY_SPECTATE`, carrying the
+/// matching secret from the host's activity.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ActivitySecretData {
+    pub secret: String,
+}
+
+/// Payload of `ACTIVITY_JOIN_REQUEST`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ActivityJoinRequestData {
+    pub user: User,
+}
@@ -10,8 +10,9 @@ pub struct AuthData {
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct Application {
     pub description: String,
-    pub icon: String,
+    pub icon: Option<String>,
     pub id: String,
+    #[serde(default)]
     pub rpc_origins: Vec<String>,
     pub name: String,
 }
@@ -21,5 +22,5 @@ pub struct User {
     pub username: String,
     pub discriminator: String,
     pub id: String,
-    pub avatar: String,
+    pub avatar: Option<String>,
 }
@@ -1,16 +1,52 @@
-use std::collections::HashMap;
-
-use serde::{Deserialize, Serialize};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
 
-use crate::models::commands::SpeakingData;
+use crate::models::commands::{
+    ChannelData, PartialChannel, PartialGuild, SpeakingData, VoiceSettings,
+};
+use crate::models::shared::{User, VoiceState};
 
+use super::activity::{ActivityJoinRequestData, ActivitySecretData};
 use super::error::ErrorData;
+use super::guild::GuildStatusData;
 use super::login::LoginData;
+use super::message::{MessageData, NotificationData};
 use super::ready::ReadyData;
+use super::voice_channel_select::VoiceChannelSelectData;
+use super::voice_connection_status::VoiceConnectionStatusData;
+
+/// The `evt` of every typed variant, any other event becomes `Unknown`.
+const TYPED_EVENTS: &[&str] = &[
+    "READY",
+    "LOGIN",
+    "ERROR",
+    "SPEAKING_START",
+    "SPEAKING_STOP",
+    "GET_SELECTED_VOICE_CHANNEL",
+    "VOICE_STATE_UPDATE",
+    "VOICE_STATE_CREATE",
+    "VOICE_STATE_DELETE",
+    "ACTIVITY_JOIN",
+    "ACTIVITY_SPECTATE",
+    "ACTIVITY_JOIN_REQUEST",
+    "VOICE_SETTINGS_UPDATE",
+    "VOICE_CHANNEL_SELECT",
+    "VOICE_CONNECTION_STATUS",
+    "CURRENT_USER_UPDATE",
+    "GUILD_STATUS",
+    "GUILD_CREATE",
+    "CHANNEL_CREATE",
+    "MESSAGE_CREATE",
+    "MESSAGE_UPDATE",
+    "MESSAGE_DELETE",
+    "NOTIFICATION_CREATE",
+];
 
+// `remote = "Self"` keeps the derived impls as inherent functions, so the
+// trait impls below can fall back to `Unknown`
 #[derive(Serialize, Deserialize, Debug)]
-#[serde(tag = "evt")]
+#[serde(remote = "Self", tag = "evt")]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum EventPayload {
     Ready {
@@ -32,20 +68,112 @@ pub enum EventPayload {
         data: SpeakingData,
     },
 
-    // TODO: type these payloads
     GetSelectedVoiceChannel {
-        data: HashMap<String, Value>,
+        data: Option<ChannelData>,
     },
+    /// a user's voice state changed in the subscribed channel
     VoiceStateUpdate {
-        data: HashMap<String, Value>,
+        data: VoiceState,
     },
+    /// a user joined the subscribed channel
     VoiceStateCreate {
-        data: HashMap<String, Value>,
+        data: VoiceState,
     },
+    /// a user left the subscribed channel
     VoiceStateDelete {
-        data: HashMap<String, Value>,
+        data: VoiceState,
+    },
+    /// the user joined a game through the activity's join secret
+    ActivityJoin {
+        data: ActivitySecretData,
+    },
+    /// the user started spectating through the activity's spectate secret
+    ActivitySpectate {
+        data: ActivitySecretData,
+    },
+    /// another user asked to join the user's activity
+    ActivityJoinRequest {
+        data: ActivityJoinRequestData,
+    },
+    /// the user's voice settings changed
+    VoiceSettingsUpdate {
+        data: VoiceSettings,
     },
+    /// the local user joined or left a voice channel
     VoiceChannelSelect {
-        data: HashMap<String, Value>,
+        data: VoiceChannelSelectData,
+    },
+    /// the connection to the voice server changed or was pinged
+    VoiceConnectionStatus {
+        data: VoiceConnectionStatusData,
+    },
+    /// the logged in user changed, e.g. their avatar
+    CurrentUserUpdate {
+        data: User,
+    },
+    /// the subscribed guild was updated
+    GuildStatus {
+        data: GuildStatusData,
+    },
+    /// the user joined a guild
+    GuildCreate {
+        data: PartialGuild,
+    },
+    /// a channel was created in a guild the user is in
+    ChannelCreate {
+        data: PartialChannel,
     },
+    /// a message was sent in the subscribed channel
+    MessageCreate {
+        data: MessageData,
+    },
+    /// a message was edited in the subscribed channel
+    MessageUpdate {
+        data: MessageData,
+    },
+    /// a message was deleted in the subscribed channel
+    MessageDelete {
+        data: MessageData,
+    },
+    /// the user received a notification
+    NotificationCreate {
+        data: NotificationData,
+    },
+    /// An event this crate has no type for. `raw` is the whole frame.
+    #[serde(skip)]
+    Unknown {
+        evt: String,
+        raw: Value,
+    },
+}
+
+impl<'de> Deserialize<'de> for EventPayload {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = Value::deserialize(deserializer)?;
+        let evt = match raw.get("evt").and_then(Value::as_str) {
+            Some(evt) => evt.to_string(),
+            None => return Err(D::Error::missing_field("evt")),
+        };
+
+        if !TYPED_EVENTS.contains(&evt.as_str()) {
+            return Ok(EventPayload::Unknown { evt, raw });
+        }
+        // a typed event that does not decode is an error, not a new event
+        EventPayload::deserialize(&raw).map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for EventPayload {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            EventPayload::Unknown { raw, .. } => raw.serialize(serializer),
+            event => EventPayload::serialize(event, serializer),
+        }
+    }
 }
@@ -1,7 +1,16 @@
 use serde::{Deserialize, Serialize};
 
+use crate::errors::RpcErrorCode;
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ErrorData {
+    pub code: RpcErrorCode,
+    pub message: String,
+}
+
+/// Payload of a `Close` frame.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CloseData {
     pub code: u32,
     pub message: String,
 }
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::commands::PartialGuild;
+
+/// Payload of `GUILD_STATUS`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GuildStatusData {
+    pub guild: PartialGuild,
+    /// Always 0, kept for compatibility with the api
+    #[serde(default)]
+    pub online: u32,
+}
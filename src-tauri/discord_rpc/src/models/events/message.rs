@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::shared::User;
+
+/// A message in a text channel.
+///
+/// Deleted messages only carry their `id`, every other field is left at
+/// its default then.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Message {
+    pub id: String,
+    #[serde(default)]
+    pub content: String,
+    #[serde(default)]
+    pub author: Option<User>,
+    /// The author's nickname in the guild
+    #[serde(default)]
+    pub nick: Option<String>,
+    /// Color of the author's top role, e.g. `#ffffff`
+    #[serde(default)]
+    pub author_color: Option<String>,
+    #[serde(default)]
+    pub timestamp: Option<String>,
+    #[serde(default)]
+    pub edited_timestamp: Option<String>,
+    /// This is the "type" field that comes from the discord api
+    #[serde(default, rename = "type")]
+    pub message_type: u32,
+    #[serde(default)]
+    pub tts: bool,
+    #[serde(default)]
+    pub pinned: bool,
+    /// Whether the author is blocked by the user
+    #[serde(default)]
+    pub blocked: bool,
+    #[serde(default)]
+    pub bot: bool,
+    #[serde(default)]
+    pub mentions: Vec<User>,
+    #[serde(default)]
+    pub mention_roles: Vec<String>,
+    #[serde(default)]
+    pub embeds: Vec<Value>,
+    #[serde(default)]
+    pub attachments: Vec<Value>,
+}
+
+/// Payload of `MESSAGE_CREATE`, `MESSAGE_UPDATE` and `MESSAGE_DELETE`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MessageData {
+    pub channel_id: String,
+    pub message: Message,
+}
+
+/// Payload of `NOTIFICATION_CREATE`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NotificationData {
+    pub channel_id: String,
+    pub message: Message,
+    pub icon_url: Option<String>,
+    pub title: String,
+    pub body: String,
+}
@@ -1,13 +1,23 @@
+mod activity;
 mod authenticate;
 mod base;
 mod error;
+mod guild;
 mod login;
+mod message;
 mod ready;
 mod selected_channel;
+mod voice_channel_select;
+mod voice_connection_status;
 
+pub use activity::*;
 pub use authenticate::*;
 pub use base::EventPayload;
 pub use error::*;
+pub use guild::*;
 pub use login::*;
+pub use message::*;
 pub use ready::*;
 pub use selected_channel::*;
+pub use voice_channel_select::*;
+pub use voice_connection_status::*;
@@ -7,14 +7,14 @@ pub struct ReadyEvent {
     pub data: ReadyData,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Config {
     pub cdn_host: String,
     pub api_endpoint: String,
     pub environment: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct ReadyData {
     pub v: u32,
     pub config: Config,
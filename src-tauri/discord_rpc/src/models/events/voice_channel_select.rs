@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+/// Payload of `VOICE_CHANNEL_SELECT`, both ids are `None` when the
+/// user left voice.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VoiceChannelSelectData {
+    pub channel_id: Option<String>,
+    pub guild_id: Option<String>,
+}
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum VoiceConnectionState {
+    Disconnected,
+    AwaitingEndpoint,
+    Authenticating,
+    Connecting,
+    Connected,
+    VoiceDisconnected,
+    VoiceConnecting,
+    VoiceConnected,
+    NoRoute,
+    IceChecking,
+}
+
+/// A ping to the voice server.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VoicePing {
+    /// Unix timestamp of the ping, in milliseconds
+    pub time: u64,
+    /// Round trip time, in milliseconds
+    pub value: u32,
+}
+
+/// Payload of `VOICE_CONNECTION_STATUS`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VoiceConnectionStatusData {
+    pub state: VoiceConnectionState,
+    pub hostname: Option<String>,
+    #[serde(default)]
+    pub pings: Vec<VoicePing>,
+    pub average_ping: Option<f64>,
+    pub last_ping: Option<f64>,
+}
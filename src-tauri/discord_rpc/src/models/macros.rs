@@ -1,9 +1,10 @@
 #[macro_export]
 macro_rules! pub_struct {
-    ($name:ident {$($field:ident: $t:ty,)*}) => {
-        #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    ($name:ident {$($(#[$attr:meta])* $field:ident: $t:ty,)*}) => {
+        #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
         pub struct $name {
             $(
+                $(#[$attr])*
                 #[serde(skip_serializing_if = "Option::is_none")]
                 pub $field: Option<$t>
             ),*
@@ -3,34 +3,39 @@ use serde_json::Value;
 
 use crate::Result;
 
-use super::commands::SetActivityArgs;
+use super::commands::{
+    AuthenticateArgs, AuthorizeArgs, CloseActivityJoinRequestArgs, GetChannelArgs, GetChannelsArgs,
+    GetGuildArgs, GetImageArgs, SelectTextChannelArgs, SelectVoiceChannelArgs,
+    SendActivityJoinInviteArgs, SetActivityArgs, SetVoiceSettingsArgs,
+};
 use super::rpc_event::RPCEvent;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[allow(clippy::large_enum_variant)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE", tag = "cmd", content = "args")]
 pub enum RPCCommand {
     Dispatch,
-    Authorize,
-    Authenticate,
-    GetGuild,
+    Authorize(AuthorizeArgs),
+    Authenticate(AuthenticateArgs),
+    GetGuild(GetGuildArgs),
     GetGuilds,
-    GetChannel,
-    GetChannels,
+    GetChannel(GetChannelArgs),
+    GetChannels(GetChannelsArgs),
     CreateChannelInvite,
     GetRelationships,
     GetUser,
     Subscribe(RPCEvent),
-    Unsubscribe,
+    Unsubscribe(RPCEvent),
     SetUserVoiceSettings,
-    SelectVoiceChannel { channel_id: String },
+    SelectVoiceChannel(SelectVoiceChannelArgs),
     GetSelectedVoiceChannel,
-    SelectTextChannel,
+    SelectTextChannel(SelectTextChannelArgs),
     GetVoiceSettings,
-    SetVoiceSettings,
+    SetVoiceSettings(SetVoiceSettingsArgs),
     CaptureShortcut,
     SetActivity(SetActivityArgs),
-    SendActivityJoinInvite,
-    CloseActivityJoinRequest,
+    SendActivityJoinInvite(SendActivityJoinInviteArgs),
+    CloseActivityJoinRequest(CloseActivityJoinRequestArgs),
     ActivityInviteUser,
     AcceptActivityInvite,
     InviteBrowser,
@@ -41,7 +46,7 @@ pub enum RPCCommand {
     Overlay,
     BrowserHandoff,
     SetCertifiedDevices,
-    GetImage,
+    GetImage(GetImageArgs),
     CreateLobby,
     UpdateLobby,
     DeleteLobby,
@@ -71,12 +76,16 @@ pub enum RPCCommand {
 
 impl RPCCommand {
     pub(crate) fn to_json(&self) -> Result<Value> {
-        let command_json = match self {
-            Self::Subscribe(event) => {
+        let mut command_json = match self {
+            Self::Subscribe(event) | Self::Unsubscribe(event) => {
+                let cmd = match self {
+                    Self::Subscribe(_) => "SUBSCRIBE",
+                    _ => "UNSUBSCRIBE",
+                };
                 let mut event_json = serde_json::to_value(event)?;
                 match &mut event_json {
                     serde_json::Value::Object(object) => {
-                        object.insert("cmd".to_string(), "SUBSCRIBE".into());
+                        object.insert("cmd".to_string(), cmd.into());
                         object
                     }
                     _ => panic!("Expected event to be an object"),
@@ -85,6 +94,14 @@ impl RPCCommand {
             }
             _ => serde_json::to_value(self)?,
         };
+
+        // commands without arguments still have to send an args object
+        if let Value::Object(object) = &mut command_json {
+            object
+                .entry("args")
+                .or_insert_with(|| Value::Object(Default::default()));
+        }
+
         //println!("{}", serde_json::to_string_pretty(&command_json)?);
         Ok(command_json)
     }
@@ -1,18 +1,28 @@
 use serde::{Deserialize, Serialize};
 
 // TODO: move this to somewhere else
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE", tag = "evt", content = "args")]
 pub enum RPCEvent {
     CurrentUserUpdate,
+    GuildStatus { guild_id: String },
+    GuildCreate,
+    ChannelCreate,
     VoiceChannelSelect,
-    VoiceStateCreate,
-    VoiceStateDelete,
-    VoiceStateUpdate,
+    VoiceStateCreate { channel_id: String },
+    VoiceStateDelete { channel_id: String },
+    VoiceStateUpdate { channel_id: String },
     VoiceSettingsUpdate,
     VoiceConnectionStatus,
     SpeakingStart { channel_id: String },
     SpeakingStop { channel_id: String },
+    MessageCreate { channel_id: String },
+    MessageUpdate { channel_id: String },
+    MessageDelete { channel_id: String },
+    NotificationCreate,
+    ActivityJoin,
+    ActivitySpectate,
+    ActivityJoinRequest,
     Ready,
     Error,
 }
@@ -1,13 +1,53 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct User {
     pub id: String,
     pub username: String,
     pub discriminator: String,
-    pub avatar: String,
+    pub avatar: Option<String>,
     pub avatar_decoration: Option<String>,
+    #[serde(default)]
     pub bot: bool,
+    #[serde(default)]
     pub flags: u32,
     pub premium_type: Option<u32>,
 }
+
+impl User {
+    /// Builds the url of the user's avatar on the given CDN host.
+    ///
+    /// Animated avatars are returned as gif, users without an avatar get
+    /// their default avatar.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let ready = ipc_client.ready();
+    /// let url = ready.user.avatar_url(&ready.config.cdn_host);
+    /// ```
+    pub fn avatar_url(&self, cdn_host: &str) -> String {
+        match &self.avatar {
+            Some(hash) => {
+                let extension = if hash.starts_with("a_") { "gif" } else { "png" };
+                format!(
+                    "https://{}/avatars/{}/{}.{}",
+                    cdn_host, self.id, hash, extension
+                )
+            }
+            None => format!(
+                "https://{}/embed/avatars/{}.png",
+                cdn_host,
+                self.default_avatar_index()
+            ),
+        }
+    }
+
+    /// Index of the default avatar, which depends on the discriminator for
+    /// legacy usernames and on the user id otherwise.
+    fn default_avatar_index(&self) -> u64 {
+        match self.discriminator.parse::<u64>() {
+            Ok(discriminator) if discriminator != 0 => discriminator % 5,
+            _ => self.id.parse::<u64>().map_or(0, |id| (id >> 22) % 6),
+        }
+    }
+}
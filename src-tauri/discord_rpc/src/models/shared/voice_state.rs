@@ -1,24 +1,26 @@
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
 
-#[derive(Serialize, Deserialize, Debug)]
+use super::User;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct VoiceState {
     pub nick: String,
     pub mute: bool,
-    pub volume: u8,
+    /// Local volume of the user, from 0 to 200
+    pub volume: f32,
     pub pan: VoicePan,
     #[serde(rename = "voice_state")]
     pub state: VoiceStateData,
-    pub user: Option<Value>,
+    pub user: Option<User>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct VoicePan {
-    pub left: u8,
-    pub right: u8,
+    pub left: f32,
+    pub right: f32,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct VoiceStateData {
     pub mute: bool,
     pub deaf: bool,
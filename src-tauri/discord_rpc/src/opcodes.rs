@@ -1,7 +1,27 @@
+use std::convert::TryFrom;
+
+use crate::errors::DiscordRPCError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OPCODES {
-    Handshake,
-    Frame,
-    Close,
-    Ping,
-    Pong,
+    Handshake = 0,
+    Frame = 1,
+    Close = 2,
+    Ping = 3,
+    Pong = 4,
+}
+
+impl TryFrom<u32> for OPCODES {
+    type Error = DiscordRPCError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Handshake),
+            1 => Ok(Self::Frame),
+            2 => Ok(Self::Close),
+            3 => Ok(Self::Ping),
+            4 => Ok(Self::Pong),
+            _ => Err(DiscordRPCError::UnknownOpcode(value)),
+        }
+    }
 }
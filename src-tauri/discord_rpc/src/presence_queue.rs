@@ -0,0 +1,190 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use crate::ipc::Connection;
+use crate::models::commands::{Activity, SetActivityArgs};
+use crate::models::rpc_command::RPCCommand;
+use crate::Result;
+
+/// How many activity updates Discord accepts in a time window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub updates: u32,
+    pub per: Duration,
+}
+
+impl RateLimit {
+    pub fn new(updates: u32, per: Duration) -> Self {
+        Self { updates, per }
+    }
+}
+
+impl Default for RateLimit {
+    /// Discord's limit of 5 updates per 20 seconds.
+    fn default() -> Self {
+        Self::new(5, Duration::from_secs(20))
+    }
+}
+
+/// An activity update Discord accepted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delivery {
+    /// The activity that was set, `None` if it was cleared.
+    pub activity: Option<Activity>,
+    pub at: SystemTime,
+}
+
+/// Progress of a [`PresenceQueue`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PresenceReport {
+    /// The last update that landed.
+    pub delivered: Option<Delivery>,
+    /// Whether an update is still waiting to be sent.
+    pub pending: bool,
+    /// Why the last attempt failed, cleared by the next delivery.
+    pub last_error: Option<String>,
+}
+
+/// Sends activity updates within Discord's rate limit,
+/// see [`DiscordIPCClient::presence_queue`](crate::DiscordIPCClient::presence_queue).
+///
+/// Updates made while waiting for the rate limit replace each other, so
+/// only the latest one is sent. An update identical to the last delivered
+/// one is skipped. Dropping the queue discards anything not yet sent.
+pub struct PresenceQueue {
+    /// The latest update, `None` until the first one. The inner `None`
+    /// clears the activity.
+    latest: watch::Sender<Option<Option<Activity>>>,
+    report: Arc<watch::Sender<PresenceReport>>,
+    task: JoinHandle<()>,
+}
+
+impl PresenceQueue {
+    pub(crate) fn new(connection: Connection, limit: RateLimit) -> Self {
+        let (latest, latest_receiver) = watch::channel(None);
+        let report = Arc::new(watch::channel(PresenceReport::default()).0);
+        let task = tokio::spawn(run(connection, latest_receiver, report.clone(), limit));
+
+        Self {
+            latest,
+            report,
+            task,
+        }
+    }
+
+    /// Queues `activity`, replacing any update that was not sent yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiscordRPCError::InvalidActivity`] if Discord would reject
+    /// the activity, nothing is queued then.
+    ///
+    /// [`DiscordRPCError::InvalidActivity`]: crate::errors::DiscordRPCError::InvalidActivity
+    pub fn set(&self, activity: Activity) -> Result<()> {
+        activity.validate()?;
+        self.push(Some(activity));
+        Ok(())
+    }
+
+    /// Queues clearing the activity, replacing any update that was not
+    /// sent yet.
+    pub fn clear(&self) {
+        self.push(None);
+    }
+
+    /// Returns the current progress of the queue.
+    pub fn report(&self) -> PresenceReport {
+        self.report.borrow().clone()
+    }
+
+    /// Returns a receiver that is notified whenever the report changes.
+    pub fn reports(&self) -> watch::Receiver<PresenceReport> {
+        self.report.subscribe()
+    }
+
+    /// Waits until every queued update was sent or skipped.
+    pub async fn flush(&self) -> PresenceReport {
+        let mut reports = self.report.subscribe();
+        loop {
+            let report = reports.borrow_and_update().clone();
+            if !report.pending || reports.changed().await.is_err() {
+                return report;
+            }
+        }
+    }
+
+    fn push(&self, update: Option<Activity>) {
+        // mark it pending first, so the task cannot clear the flag after
+        // picking the update up and leave it set for good
+        self.report.send_modify(|report| {
+            report.pending = true;
+            self.latest.send_replace(Some(update));
+        });
+    }
+}
+
+impl Drop for PresenceQueue {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn run(
+    connection: Connection,
+    mut latest: watch::Receiver<Option<Option<Activity>>>,
+    report: Arc<watch::Sender<PresenceReport>>,
+    limit: RateLimit,
+) {
+    let mut sent: VecDeque<Instant> = VecDeque::new();
+    let mut last: Option<Option<Activity>> = None;
+
+    while latest.changed().await.is_ok() {
+        // wait for a free slot, updates arriving meanwhile are coalesced
+        while let Some(&oldest) = sent.front() {
+            if oldest.elapsed() >= limit.per {
+                sent.pop_front();
+            } else if sent.len() >= limit.updates as usize {
+                tokio::time::sleep_until(oldest + limit.per).await;
+            } else {
+                break;
+            }
+        }
+
+        let update = match latest.borrow_and_update().clone() {
+            Some(update) => update,
+            None => continue,
+        };
+
+        if last.as_ref() != Some(&update) {
+            let command = RPCCommand::SetActivity(match update.clone() {
+                Some(activity) => SetActivityArgs::new(activity),
+                None => SetActivityArgs::default(),
+            });
+            let result = connection.request(&command).await;
+            sent.push_back(Instant::now());
+
+            match result {
+                Ok(_) => {
+                    report.send_modify(|report| {
+                        report.delivered = Some(Delivery {
+                            activity: update.clone(),
+                            at: SystemTime::now(),
+                        });
+                        report.last_error = None;
+                    });
+                    last = Some(update);
+                }
+                Err(e) => report.send_modify(|report| report.last_error = Some(e.to_string())),
+            }
+        }
+
+        // checked under the report's lock, so a concurrent push is either
+        // seen here or marks itself pending afterwards
+        report.send_modify(|report| report.pending = latest.has_changed().unwrap_or(false));
+    }
+}
@@ -0,0 +1,165 @@
+use crate::errors::DiscordRPCError;
+use crate::opcodes::OPCODES;
+use crate::Result;
+use crate::{pack, unpack};
+
+/// Size of the `(opcode, length)` header in front of every frame.
+pub const HEADER_SIZE: usize = 8;
+
+/// Largest payload accepted unless configured otherwise. Generous enough
+/// for image data uris and large guild lists.
+pub const DEFAULT_MAX_PAYLOAD: usize = 8 * 1024 * 1024;
+
+/// A single IPC frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub opcode: OPCODES,
+    pub payload: String,
+}
+
+impl Frame {
+    pub fn new(opcode: OPCODES, payload: impl Into<String>) -> Self {
+        Self {
+            opcode,
+            payload: payload.into(),
+        }
+    }
+
+    /// Serializes the frame into the bytes written to the socket.
+    pub fn encode(&self) -> Vec<u8> {
+        encode_frame(self.opcode, &self.payload)
+    }
+
+    /// Like [`encode`](Frame::encode), but refuses payloads larger than
+    /// `max_payload`.
+    pub fn encode_bounded(&self, max_payload: usize) -> Result<Vec<u8>> {
+        check_length(self.payload.len(), max_payload)?;
+        Ok(self.encode())
+    }
+}
+
+/// Serializes a frame into the bytes written to the socket.
+///
+/// # Examples
+/// ```
+/// use discord_rpc::opcodes::OPCODES;
+/// use discord_rpc::protocol::encode_frame;
+///
+/// let bytes = encode_frame(OPCODES::Frame, "{}");
+/// assert_eq!(bytes, [1, 0, 0, 0, 2, 0, 0, 0, b'{', b'}']);
+/// ```
+pub fn encode_frame(opcode: OPCODES, payload: &str) -> Vec<u8> {
+    let mut packet = pack(opcode as u32, payload.len() as u32).expect("packing cannot fail");
+    packet.extend(payload.as_bytes());
+    packet
+}
+
+/// Splits a byte stream back into frames.
+///
+/// Bytes can be pushed in chunks of any size, frames are handed out once
+/// they have been read completely. The length in a frame's header is
+/// checked against the maximum payload before any of it is buffered, so a
+/// corrupt header cannot make the decoder allocate gigabytes.
+#[derive(Debug)]
+pub struct FrameDecoder {
+    buffer: Vec<u8>,
+    max_payload: usize,
+}
+
+impl Default for FrameDecoder {
+    fn default() -> Self {
+        Self::with_max_payload(DEFAULT_MAX_PAYLOAD)
+    }
+}
+
+impl FrameDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a decoder that rejects payloads larger than `max_payload`.
+    pub fn with_max_payload(max_payload: usize) -> Self {
+        Self {
+            buffer: Vec::new(),
+            max_payload,
+        }
+    }
+
+    pub fn max_payload(&self) -> usize {
+        self.max_payload
+    }
+
+    /// Appends bytes read from the socket.
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Returns the next complete frame, or `None` if more bytes are needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiscordRPCError::FrameTooLarge`] if the header announces a
+    /// payload above the maximum. The stream cannot be resynchronized after
+    /// that, so the buffer is dropped and the connection should be too.
+    ///
+    /// Returns an `Err` variant if the frame has an unknown opcode or its
+    /// payload is not valid UTF-8. The frame is consumed either way.
+    pub fn decode(&mut self) -> Result<Option<Frame>> {
+        if self.buffer.len() < HEADER_SIZE {
+            return Ok(None);
+        }
+
+        let (opcode, length) = unpack(self.buffer[..HEADER_SIZE].to_vec())?;
+        if let Err(e) = check_length(length as usize, self.max_payload) {
+            self.buffer.clear();
+            return Err(e);
+        }
+
+        let end = HEADER_SIZE + length as usize;
+        if self.buffer.len() < end {
+            return Ok(None);
+        }
+
+        let payload = self.buffer[HEADER_SIZE..end].to_vec();
+        self.buffer.drain(..end);
+
+        Ok(Some(Frame {
+            opcode: OPCODES::try_from(opcode)?,
+            payload: String::from_utf8(payload)?,
+        }))
+    }
+
+    /// Checks that the stream did not end in the middle of a frame, call
+    /// this once the peer closed the connection.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiscordRPCError::TruncatedFrame`] if a partial frame is
+    /// still buffered.
+    pub fn finish(&self) -> Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        let expected = match self.buffer.get(..HEADER_SIZE) {
+            Some(header) => HEADER_SIZE + unpack(header.to_vec())?.1 as usize,
+            None => HEADER_SIZE,
+        };
+        Err(DiscordRPCError::TruncatedFrame {
+            expected,
+            received: self.buffer.len(),
+        })
+    }
+
+    /// Drops any partially received frame, e.g. after reconnecting.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+}
+
+fn check_length(length: usize, max: usize) -> Result<()> {
+    if length > max {
+        return Err(DiscordRPCError::FrameTooLarge { length, max });
+    }
+    Ok(())
+}
@@ -0,0 +1,107 @@
+//! Runtime independent implementation of the Discord IPC protocol.
+//!
+//! Nothing in here touches a socket: bytes read from the IPC are fed in and
+//! bytes to write are handed back. The tokio and blocking clients are thin
+//! I/O layers around it.
+
+mod codec;
+mod session;
+
+pub use codec::{encode_frame, Frame, FrameDecoder, DEFAULT_MAX_PAYLOAD, HEADER_SIZE};
+pub use session::{Session, SessionEvent, SessionState};
+
+use crate::create_json_with_nonce;
+use crate::errors::DiscordRPCError;
+use crate::models::commands::{EventFunctionPayload, SetActivityArgs};
+use crate::models::events::{CloseData, ErrorData, ReadyData};
+use crate::models::rpc_command::RPCCommand;
+use crate::Result;
+use serde_json::{json, Value};
+
+/// Version of the IPC protocol sent in the handshake.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Returns the payload of the handshake frame for `client_id`.
+pub fn handshake(client_id: &str) -> String {
+    json!({
+      "v": PROTOCOL_VERSION,
+      "client_id": client_id
+    })
+    .to_string()
+}
+
+/// Serializes a command into a frame payload carrying `nonce`.
+///
+/// # Errors
+///
+/// Returns [`DiscordRPCError::InvalidActivity`] if the command sets an
+/// activity Discord would reject.
+pub fn encode_command(command: &RPCCommand, nonce: String) -> Result<String> {
+    if let RPCCommand::SetActivity(SetActivityArgs {
+        activity: Some(activity),
+        ..
+    }) = command
+    {
+        activity.validate()?;
+    }
+
+    let mut command_json = command.to_json()?;
+    create_json_with_nonce(&mut command_json, nonce)
+}
+
+/// Returns the nonce of a frame payload, if it has one.
+pub fn response_nonce(value: &Value) -> Option<&str> {
+    value.get("nonce").and_then(Value::as_str)
+}
+
+/// Turns a command response into its payload, or into an error if
+/// Discord answered with an `ERROR` event.
+pub fn parse_response(value: Value) -> Result<EventFunctionPayload> {
+    if value.get("evt").and_then(Value::as_str) == Some("ERROR") {
+        let error: ErrorData = serde_json::from_value(value["data"].clone())?;
+        return Err(DiscordRPCError::Rpc {
+            code: error.code,
+            message: error.message,
+            nonce: response_nonce(&value).map(String::from),
+        });
+    }
+
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Checks the reply to a handshake, returning the `READY` event's data.
+pub fn parse_ready(payload: &str) -> Result<ReadyData> {
+    let value: Value = serde_json::from_str(payload)?;
+    match value.get("evt").and_then(Value::as_str) {
+        Some("READY") => Ok(serde_json::from_value(value["data"].clone())?),
+        Some("ERROR") => {
+            let error: ErrorData = serde_json::from_value(value["data"].clone())?;
+            Err(DiscordRPCError::HandshakeFailed {
+                code: error.code.into(),
+                message: error.message,
+            })
+        }
+        _ => Err(DiscordRPCError::UnexpectedResponse),
+    }
+}
+
+/// Turns the payload of a `Close` frame into a [`DiscordRPCError::Closed`].
+pub fn parse_close(payload: &str) -> DiscordRPCError {
+    match serde_json::from_str::<CloseData>(payload) {
+        Ok(close) => DiscordRPCError::Closed {
+            code: close.code,
+            message: close.message,
+        },
+        Err(e) => e.into(),
+    }
+}
+
+/// Discord answers an invalid handshake by closing the connection.
+pub fn handshake_error(error: DiscordRPCError) -> DiscordRPCError {
+    match error {
+        DiscordRPCError::Closed { code, message } => {
+            DiscordRPCError::HandshakeFailed { code, message }
+        }
+        e => e,
+    }
+}
@@ -0,0 +1,261 @@
+use super::{
+    encode_command, encode_frame, handshake, handshake_error, parse_close, parse_ready,
+    parse_response, response_nonce, Frame, FrameDecoder, DEFAULT_MAX_PAYLOAD,
+};
+use crate::errors::DiscordRPCError;
+use crate::models::commands::EventFunctionPayload;
+use crate::models::events::ReadyData;
+use crate::models::rpc_command::RPCCommand;
+use crate::nonce;
+use crate::opcodes::OPCODES;
+use crate::Result;
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// Where a [`Session`] is in the connection lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionState {
+    /// No handshake has been sent yet.
+    Idle,
+    /// The handshake was sent, waiting for `READY`.
+    Handshaking,
+    /// `READY` was received, commands can be sent.
+    Ready,
+    /// Either side closed the connection.
+    Closed,
+}
+
+/// Something the peer sent, as returned by [`Session::poll`].
+#[derive(Debug)]
+pub enum SessionEvent {
+    /// The handshake succeeded.
+    Ready(ReadyData),
+    /// The reply to the command that was sent with `nonce`.
+    Response {
+        nonce: String,
+        result: Result<EventFunctionPayload>,
+    },
+    /// A frame that does not answer a command, such as a subscribed event.
+    /// It is not necessarily valid json.
+    Event(String),
+}
+
+/// Sans-IO state machine of a single IPC connection.
+///
+/// The caller writes whatever [`take_outgoing`] returns to the socket and
+/// feeds everything it reads into [`receive`], then drains [`poll`].
+/// `Ping` frames are answered automatically by queueing a `Pong`.
+///
+/// [`take_outgoing`]: Session::take_outgoing
+/// [`receive`]: Session::receive
+/// [`poll`]: Session::poll
+///
+/// # Examples
+/// ```ignore
+/// let mut session = Session::new("<some client id>");
+/// session.handshake();
+/// socket.write_all(&session.take_outgoing())?;
+///
+/// let n = socket.read(&mut buf)?;
+/// session.receive(&buf[..n]);
+/// while let Some(event) = session.poll()? {
+///     println!("{:?}", event);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Session {
+    client_id: String,
+    state: SessionState,
+    ready: Option<ReadyData>,
+    decoder: FrameDecoder,
+    pending: HashSet<String>,
+    outgoing: Vec<u8>,
+}
+
+impl Session {
+    pub fn new(client_id: &str) -> Self {
+        Self::with_max_payload(client_id, DEFAULT_MAX_PAYLOAD)
+    }
+
+    /// Creates a session that refuses to send or receive payloads larger
+    /// than `max_payload` bytes.
+    pub fn with_max_payload(client_id: &str, max_payload: usize) -> Self {
+        Self {
+            client_id: client_id.to_string(),
+            state: SessionState::Idle,
+            ready: None,
+            decoder: FrameDecoder::with_max_payload(max_payload),
+            pending: HashSet::new(),
+            outgoing: Vec::new(),
+        }
+    }
+
+    pub fn state(&self) -> SessionState {
+        self.state
+    }
+
+    /// Returns the `READY` event of the latest handshake.
+    pub fn ready(&self) -> Option<&ReadyData> {
+        self.ready.as_ref()
+    }
+
+    /// Returns whether a command sent with `nonce` still awaits its reply.
+    pub fn is_pending(&self, nonce: &str) -> bool {
+        self.pending.contains(nonce)
+    }
+
+    /// Starts a new connection by queueing the handshake.
+    ///
+    /// Anything left over from a previous connection is dropped, including
+    /// commands that were still waiting for a reply.
+    pub fn handshake(&mut self) {
+        self.decoder.clear();
+        self.pending.clear();
+        self.outgoing.clear();
+        self.state = SessionState::Handshaking;
+        self.queue(OPCODES::Handshake, &handshake(&self.client_id));
+    }
+
+    /// Queues a command and returns the nonce its reply will carry.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiscordRPCError::ClientClosed`] if the session is not
+    /// ready, or an `Err` variant if the command could not be serialized.
+    pub fn send_command(&mut self, command: &RPCCommand) -> Result<String> {
+        if self.state != SessionState::Ready {
+            return Err(DiscordRPCError::ClientClosed);
+        }
+
+        let nonce = nonce();
+        let payload = encode_command(command, nonce.clone())?;
+        let frame =
+            Frame::new(OPCODES::Frame, payload).encode_bounded(self.decoder.max_payload())?;
+        self.outgoing.extend(frame);
+        self.pending.insert(nonce.clone());
+
+        Ok(nonce)
+    }
+
+    /// Queues an already serialized frame payload. Its reply, if any, is
+    /// passed on as an [`SessionEvent::Event`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiscordRPCError::ClientClosed`] if the session is not
+    /// ready, or [`DiscordRPCError::FrameTooLarge`] if `payload` is too long.
+    pub fn send_payload(&mut self, payload: &str) -> Result<()> {
+        if self.state != SessionState::Ready {
+            return Err(DiscordRPCError::ClientClosed);
+        }
+
+        let frame =
+            Frame::new(OPCODES::Frame, payload).encode_bounded(self.decoder.max_payload())?;
+        self.outgoing.extend(frame);
+        Ok(())
+    }
+
+    /// Queues a `Close` frame and ends the session.
+    pub fn close(&mut self) {
+        if self.state != SessionState::Closed {
+            self.queue(OPCODES::Close, "{}");
+            self.state = SessionState::Closed;
+        }
+        self.pending.clear();
+    }
+
+    /// Returns the bytes that have to be written to the socket.
+    pub fn take_outgoing(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.outgoing)
+    }
+
+    /// Feeds bytes read from the socket.
+    pub fn receive(&mut self, bytes: &[u8]) {
+        self.decoder.push(bytes);
+    }
+
+    /// Ends the session after the peer closed the connection.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiscordRPCError::TruncatedFrame`] if the connection ended
+    /// in the middle of a frame.
+    pub fn finish(&mut self) -> Result<()> {
+        self.state = SessionState::Closed;
+        self.pending.clear();
+        self.decoder.finish()
+    }
+
+    /// Returns the next event decoded from the received bytes, or `None`
+    /// if more bytes are needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiscordRPCError::HandshakeFailed`] if the handshake was
+    /// rejected, [`DiscordRPCError::Closed`] if Discord closed the
+    /// connection, or an `Err` variant if a frame could not be decoded.
+    /// A [`DiscordRPCError::FrameTooLarge`] also ends the session, as the
+    /// stream cannot be read past it.
+    pub fn poll(&mut self) -> Result<Option<SessionEvent>> {
+        while let Some(frame) = self.decode()? {
+            match frame.opcode {
+                OPCODES::Ping => self.queue(OPCODES::Pong, &frame.payload),
+                OPCODES::Close => {
+                    let handshaking = self.state == SessionState::Handshaking;
+                    self.state = SessionState::Closed;
+                    self.pending.clear();
+                    let error = parse_close(&frame.payload);
+                    return Err(if handshaking {
+                        handshake_error(error)
+                    } else {
+                        error
+                    });
+                }
+                _ if self.state == SessionState::Handshaking => {
+                    let ready = parse_ready(&frame.payload)
+                        .inspect_err(|_| self.state = SessionState::Closed)?;
+                    self.state = SessionState::Ready;
+                    self.ready = Some(ready.clone());
+                    return Ok(Some(SessionEvent::Ready(ready)));
+                }
+                _ => return Ok(Some(self.handle_payload(frame.payload))),
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn decode(&mut self) -> Result<Option<Frame>> {
+        self.decoder.decode().inspect_err(|e| {
+            if matches!(e, DiscordRPCError::FrameTooLarge { .. }) {
+                self.state = SessionState::Closed;
+                self.pending.clear();
+            }
+        })
+    }
+
+    fn handle_payload(&mut self, payload: String) -> SessionEvent {
+        // cannot be a response, whoever reads the events reports it
+        let value: Value = match serde_json::from_str(&payload) {
+            Ok(value) => value,
+            Err(_) => return SessionEvent::Event(payload),
+        };
+        let nonce = response_nonce(&value).filter(|nonce| self.pending.contains(*nonce));
+
+        match nonce {
+            Some(nonce) => {
+                let nonce = nonce.to_string();
+                self.pending.remove(&nonce);
+                SessionEvent::Response {
+                    nonce,
+                    result: parse_response(value),
+                }
+            }
+            None => SessionEvent::Event(payload),
+        }
+    }
+
+    fn queue(&mut self, opcode: OPCODES, payload: &str) {
+        self.outgoing.extend(encode_frame(opcode, payload));
+    }
+}
@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::stream::{self, BoxStream};
+use futures::{Stream, StreamExt};
+use serde_json::Value;
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinHandle;
+
+use crate::ipc::Connection;
+use crate::models::events::EventPayload;
+use crate::models::rpc_event::RPCEvent;
+use crate::Result;
+
+/// A stream of payloads for one subscribed event,
+/// see [`DiscordIPCClient::subscribe`](crate::DiscordIPCClient::subscribe).
+///
+/// Dropping the stream releases the subscription. The stream ends when
+/// the client is closed or the event is
+/// [unsubscribed](crate::DiscordIPCClient::unsubscribe). Payloads that do not parse are skipped and passed
+/// to the [`handler`](crate::DiscordIPCClient::handler)s instead.
+pub struct EventStream {
+    inner: BoxStream<'static, EventPayload>,
+    _guard: SubscriptionGuard,
+}
+
+impl EventStream {
+    pub(crate) fn new(
+        events: broadcast::Receiver<String>,
+        closed: watch::Receiver<bool>,
+        guard: SubscriptionGuard,
+    ) -> Self {
+        let token = guard.token.as_ref();
+        let subscribed = token
+            .and_then(|token| serde_json::to_value(&token.event).ok())
+            .unwrap_or_default();
+        let ended = match token {
+            Some(token) => token.ended.clone(),
+            None => watch::channel(true).1,
+        };
+        let connection = guard.connection.clone();
+
+        let state = (events, closed, ended);
+        let inner = stream::unfold(state, move |(mut events, mut closed, mut ended)| {
+            let subscribed = subscribed.clone();
+            let connection = connection.clone();
+            async move {
+                loop {
+                    if *closed.borrow() || *ended.borrow() {
+                        return None;
+                    }
+
+                    let payload = tokio::select! {
+                        payload = events.recv() => match payload {
+                            Ok(payload) => payload,
+                            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                            Err(broadcast::error::RecvError::Closed) => return None,
+                        },
+                        _ = closed.changed() => return None,
+                        // set by unsubscribe, or the subscription is gone
+                        _ = ended.changed() => return None,
+                    };
+
+                    let value: Value = match serde_json::from_str(&payload) {
+                        Ok(value) => value,
+                        Err(_) => continue,
+                    };
+                    if !belongs_to(&subscribed, &value) {
+                        continue;
+                    }
+
+                    match serde_json::from_value::<EventPayload>(value) {
+                        Ok(event) => return Some((event, (events, closed, ended))),
+                        Err(e) => connection.report(e.into()),
+                    }
+                }
+            }
+        });
+
+        Self {
+            inner: inner.boxed(),
+            _guard: guard,
+        }
+    }
+}
+
+impl Stream for EventStream {
+    type Item = EventPayload;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+/// The task running a [`DiscordIPCClient::handler`](crate::DiscordIPCClient::handler)
+/// callback.
+///
+/// Dropping the handle stops the handler. It also stops on its own once
+/// the client is closed.
+pub struct HandlerHandle {
+    task: JoinHandle<()>,
+}
+
+impl HandlerHandle {
+    pub(crate) fn new(task: JoinHandle<()>) -> Self {
+        Self { task }
+    }
+
+    /// Returns whether the handler stopped because the client was closed.
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+impl Drop for HandlerHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// The events a client is subscribed to, so they can be subscribed again
+/// on a new connection.
+///
+/// Entries are keyed by the event's json, so the same event with different
+/// args, e.g. another `channel_id`, is tracked separately.
+#[derive(Default)]
+pub(crate) struct SubscriptionRegistry {
+    entries: HashMap<String, Subscription>,
+    /// Tells apart subscriptions to the same event made after an unsubscribe.
+    next_generation: u64,
+}
+
+struct Subscription {
+    event: RPCEvent,
+    /// Number of live streams for the event.
+    streams: usize,
+    generation: u64,
+    /// Set once the event was unsubscribed, ending its streams.
+    ended: watch::Sender<bool>,
+}
+
+/// One stream's hold on a subscription, see [`SubscriptionRegistry::add`].
+pub(crate) struct SubscriptionToken {
+    pub(crate) event: RPCEvent,
+    key: String,
+    generation: u64,
+    ended: watch::Receiver<bool>,
+}
+
+impl SubscriptionRegistry {
+    /// Records one more stream for `event`.
+    pub(crate) fn add(&mut self, event: &RPCEvent) -> Result<SubscriptionToken> {
+        let key = serde_json::to_string(event)?;
+        let next_generation = &mut self.next_generation;
+        let subscription = self.entries.entry(key.clone()).or_insert_with(|| {
+            *next_generation += 1;
+            Subscription {
+                event: event.clone(),
+                streams: 0,
+                generation: *next_generation,
+                ended: watch::channel(false).0,
+            }
+        });
+        subscription.streams += 1;
+
+        Ok(SubscriptionToken {
+            event: event.clone(),
+            key,
+            generation: subscription.generation,
+            ended: subscription.ended.subscribe(),
+        })
+    }
+
+    /// Drops the hold of `token`, returning whether it was the last one.
+    ///
+    /// Tokens of a subscription that was unsubscribed in the meantime do
+    /// nothing, even if the event was subscribed again since.
+    pub(crate) fn release(&mut self, token: &SubscriptionToken) -> bool {
+        match self.entries.get_mut(&token.key) {
+            Some(subscription) if subscription.generation == token.generation => {
+                subscription.streams -= 1;
+                if subscription.streams > 0 {
+                    return false;
+                }
+                self.entries.remove(&token.key);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Forgets `event` regardless of how many streams hold it, ending them.
+    pub(crate) fn remove(&mut self, event: &RPCEvent) -> Result<()> {
+        if let Some(subscription) = self.entries.remove(&serde_json::to_string(event)?) {
+            subscription.ended.send_replace(true);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn events(&self) -> Vec<RPCEvent> {
+        self.entries
+            .values()
+            .map(|subscription| subscription.event.clone())
+            .collect()
+    }
+}
+
+/// Unsubscribes from its event when dropped.
+pub(crate) struct SubscriptionGuard {
+    connection: Connection,
+    token: Option<SubscriptionToken>,
+}
+
+impl SubscriptionGuard {
+    pub(crate) fn new(connection: Connection, token: SubscriptionToken) -> Self {
+        Self {
+            connection,
+            token: Some(token),
+        }
+    }
+}
+
+impl Drop for SubscriptionGuard {
+    fn drop(&mut self) {
+        let token = match self.token.take() {
+            Some(token) => token,
+            None => return,
+        };
+
+        // decided right away, so a later subscribe to the same event is
+        // not undone by an unsubscribe sent after it
+        let unsubscribed = match self.connection.release(&token) {
+            Ok(Some(unsubscribed)) => unsubscribed,
+            Ok(None) => return,
+            Err(e) => return self.connection.report(e),
+        };
+
+        // the response has to be awaited asynchronously, which needs a runtime
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            let connection = self.connection.clone();
+            runtime.spawn(async move {
+                if let Err(e) = unsubscribed.await {
+                    connection.report(e);
+                }
+            });
+        }
+    }
+}
+
+/// Tells whether `payload` was sent for `subscribed`, a serialized
+/// [`RPCEvent`].
+///
+/// Besides the `evt`, every subscription argument is compared with the
+/// field of the same name in `data`, so streams for two channels do not see
+/// each other's events. Payloads without that field, such as voice states,
+/// are let through.
+fn belongs_to(subscribed: &Value, payload: &Value) -> bool {
+    if payload.get("evt") != subscribed.get("evt") {
+        return false;
+    }
+
+    let args = match subscribed.get("args").and_then(Value::as_object) {
+        Some(args) => args,
+        None => return true,
+    };
+    let data = &payload["data"];
+    args.iter().all(|(key, expected)| {
+        let actual = match key.as_str() {
+            // GUILD_STATUS carries the whole guild
+            "guild_id" => data.get(key).or_else(|| data.pointer("/guild/id")),
+            _ => data.get(key),
+        };
+        actual.is_none_or(|actual| actual == expected)
+    })
+}
@@ -0,0 +1,410 @@
+//! An in-process fake Discord client for tests.
+//!
+//! [`MockServer`] listens on a temporary Unix socket, speaks the same
+//! framing as the real IPC, answers the handshake with a `READY` event,
+//! records everything it receives and replies to commands as scripted.
+//!
+//! ```ignore
+//! let server = MockServer::start().await?;
+//! let client = DiscordIPCClient::builder("1234")
+//!     .socket_path(server.path())
+//!     .connect()
+//!     .await?;
+//! ```
+
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::unix::OwnedReadHalf;
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::{mpsc, oneshot, Notify};
+use tokio::task::JoinHandle;
+
+use crate::opcodes::OPCODES;
+use crate::{nonce, pack, unpack};
+
+/// How the server answers a command.
+#[derive(Debug, Clone)]
+pub enum MockResponse {
+    /// Reply with this `data`.
+    Data(Value),
+    /// Reply with an `ERROR` event.
+    Error { code: u32, message: String },
+    /// Do not reply at all.
+    Ignore,
+}
+
+/// A frame received by the server.
+#[derive(Debug, Clone)]
+pub struct ReceivedFrame {
+    pub opcode: u32,
+    pub payload: String,
+}
+
+impl ReceivedFrame {
+    /// The payload parsed as json, or `Value::Null` if it is not json.
+    pub fn json(&self) -> Value {
+        serde_json::from_str(&self.payload).unwrap_or(Value::Null)
+    }
+}
+
+enum Control {
+    Frame(OPCODES, String),
+    StopReading(oneshot::Sender<()>),
+    Disconnect,
+}
+
+#[derive(Default)]
+struct State {
+    frames: Vec<ReceivedFrame>,
+    responses: HashMap<String, MockResponse>,
+    reject_handshake: Option<(u32, String)>,
+    ready_data: Option<Value>,
+    connections: Vec<mpsc::UnboundedSender<Control>>,
+}
+
+/// A fake Discord IPC server, see the [module docs](self).
+pub struct MockServer {
+    path: PathBuf,
+    state: Arc<Mutex<State>>,
+    received: Arc<Notify>,
+    accept: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Starts listening on a fresh socket in the temp directory.
+    pub async fn start() -> io::Result<Self> {
+        let path = std::env::temp_dir().join(format!("discord-ipc-mock-{}", nonce()));
+        let listener = UnixListener::bind(&path)?;
+
+        let state: Arc<Mutex<State>> = Default::default();
+        let received: Arc<Notify> = Default::default();
+
+        let accept = {
+            let state = state.clone();
+            let received = received.clone();
+            tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    let stream = match stream.into_std() {
+                        Ok(stream) => stream,
+                        Err(_) => continue,
+                    };
+                    let (sender, receiver) = mpsc::unbounded_channel();
+                    state.lock().unwrap().connections.push(sender);
+                    tokio::spawn(serve(stream, receiver, state.clone(), received.clone()));
+                }
+            })
+        };
+
+        Ok(Self {
+            path,
+            state,
+            received,
+            accept,
+        })
+    }
+
+    /// The socket path to hand to
+    /// [`DiscordIPCClientBuilder::socket_path`](crate::DiscordIPCClientBuilder::socket_path).
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Scripts the reply to every later `cmd` command.
+    ///
+    /// Commands without a scripted reply are answered with their own `args`.
+    pub fn respond_to(&self, cmd: &str, response: MockResponse) {
+        self.state
+            .lock()
+            .unwrap()
+            .responses
+            .insert(cmd.to_string(), response);
+    }
+
+    /// Makes later handshakes fail with a `Close` frame.
+    pub fn reject_handshake(&self, code: u32, message: &str) {
+        self.state.lock().unwrap().reject_handshake = Some((code, message.to_string()));
+    }
+
+    /// Lets later handshakes succeed again after [`reject_handshake`].
+    ///
+    /// [`reject_handshake`]: #method.reject_handshake
+    pub fn accept_handshake(&self) {
+        self.state.lock().unwrap().reject_handshake = None;
+    }
+
+    /// Replaces the `data` of the `READY` event sent after a handshake.
+    pub fn set_ready_data(&self, data: Value) {
+        self.state.lock().unwrap().ready_data = Some(data);
+    }
+
+    /// Every frame received so far, in order.
+    pub fn frames(&self) -> Vec<ReceivedFrame> {
+        self.state.lock().unwrap().frames.clone()
+    }
+
+    /// Every handshake received so far.
+    pub fn handshakes(&self) -> Vec<Value> {
+        self.frames_with(OPCODES::Handshake)
+    }
+
+    /// Every command received so far.
+    pub fn commands(&self) -> Vec<Value> {
+        self.frames_with(OPCODES::Frame)
+    }
+
+    /// Every received command named `cmd`.
+    pub fn commands_named(&self, cmd: &str) -> Vec<Value> {
+        self.commands()
+            .into_iter()
+            .filter(|command| command["cmd"] == cmd)
+            .collect()
+    }
+
+    fn frames_with(&self, opcode: OPCODES) -> Vec<Value> {
+        self.frames()
+            .iter()
+            .filter(|frame| frame.opcode == opcode as u32)
+            .map(ReceivedFrame::json)
+            .collect()
+    }
+
+    /// Waits until `condition` holds for the received frames.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it does not hold within five seconds.
+    pub async fn wait_until<F>(&self, condition: F)
+    where
+        F: Fn(&MockServer) -> bool,
+    {
+        let wait = async {
+            loop {
+                let received = self.received.notified();
+                if condition(self) {
+                    return;
+                }
+                received.await;
+            }
+        };
+
+        if tokio::time::timeout(Duration::from_secs(5), wait)
+            .await
+            .is_err()
+        {
+            panic!("condition not met, received {:#?}", self.frames());
+        }
+    }
+
+    /// Waits until at least `count` commands named `cmd` were received and returns them.
+    pub async fn wait_for_commands(&self, cmd: &str, count: usize) -> Vec<Value> {
+        self.wait_until(|server| server.commands_named(cmd).len() >= count)
+            .await;
+        self.commands_named(cmd)
+    }
+
+    /// Sends a `DISPATCH` event to every connected client.
+    pub fn send_event(&self, evt: &str, data: Value) {
+        let payload = json!({ "cmd": "DISPATCH", "evt": evt, "data": data, "nonce": null });
+        self.send_frame(OPCODES::Frame, &payload.to_string());
+    }
+
+    /// Sends a raw frame to every connected client.
+    pub fn send_frame(&self, opcode: OPCODES, payload: &str) {
+        self.broadcast(|| Control::Frame(opcode, payload.to_string()));
+    }
+
+    /// Drops every open connection, as if Discord was restarted.
+    pub fn disconnect(&self) {
+        self.broadcast(|| Control::Disconnect);
+    }
+
+    /// Stops reading from every open connection while keeping it open, so
+    /// a client only notices the loss once its next write fails.
+    pub async fn stop_reading(&self) {
+        let mut stopped = Vec::new();
+        self.broadcast(|| {
+            let (sender, receiver) = oneshot::channel();
+            stopped.push(receiver);
+            Control::StopReading(sender)
+        });
+        for receiver in stopped {
+            let _ = receiver.await;
+        }
+    }
+
+    fn broadcast<F>(&self, mut control: F)
+    where
+        F: FnMut() -> Control,
+    {
+        self.state
+            .lock()
+            .unwrap()
+            .connections
+            .retain(|connection| connection.send(control()).is_ok());
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.accept.abort();
+        self.disconnect();
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn default_ready_data() -> Value {
+    json!({
+        "v": 1,
+        "config": {
+            "cdn_host": "cdn.discordapp.com",
+            "api_endpoint": "//discord.com/api",
+            "environment": "production"
+        },
+        "user": {
+            "id": "123456789012345678",
+            "username": "mock",
+            "discriminator": "0001",
+            "avatar": "a_0123456789abcdef",
+            "avatar_decoration": null,
+            "bot": false,
+            "flags": 0,
+            "premium_type": 0
+        }
+    })
+}
+
+async fn read_frame(socket: &mut OwnedReadHalf) -> io::Result<ReceivedFrame> {
+    let mut header = [0u8; 8];
+    socket.read_exact(&mut header).await?;
+    let (opcode, length) =
+        unpack(header.to_vec()).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    let mut data = vec![0u8; length as usize];
+    socket.read_exact(&mut data).await?;
+
+    Ok(ReceivedFrame {
+        opcode,
+        payload: String::from_utf8_lossy(&data).into_owned(),
+    })
+}
+
+async fn serve(
+    stream: std::os::unix::net::UnixStream,
+    mut control: mpsc::UnboundedReceiver<Control>,
+    state: Arc<Mutex<State>>,
+    received: Arc<Notify>,
+) {
+    // kept around to shut the reading side down, see `MockServer::stop_reading`
+    let shutdown = match stream.try_clone() {
+        Ok(shutdown) => shutdown,
+        Err(_) => return,
+    };
+    let (mut read_half, mut write_half) = match UnixStream::from_std(stream) {
+        Ok(stream) => stream.into_split(),
+        Err(_) => return,
+    };
+
+    let (frames_sender, mut frames) = mpsc::unbounded_channel();
+    let reader = tokio::spawn(async move {
+        while let Ok(frame) = read_frame(&mut read_half).await {
+            if frames_sender.send(frame).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut reading = true;
+    loop {
+        let outgoing = tokio::select! {
+            frame = frames.recv(), if reading => match frame {
+                Some(frame) => {
+                    let replies = reply_to(&frame, &state);
+                    state.lock().unwrap().frames.push(frame);
+                    received.notify_waiters();
+                    replies
+                }
+                None => break,
+            },
+            message = control.recv() => match message {
+                Some(Control::Frame(opcode, payload)) => vec![(opcode, payload)],
+                Some(Control::StopReading(stopped)) => {
+                    reading = false;
+                    let _ = shutdown.shutdown(std::net::Shutdown::Read);
+                    let _ = stopped.send(());
+                    Vec::new()
+                }
+                Some(Control::Disconnect) | None => break,
+            },
+        };
+
+        for (opcode, payload) in outgoing {
+            let mut packet = match pack(opcode as u32, payload.len() as u32) {
+                Ok(packet) => packet,
+                Err(_) => continue,
+            };
+            packet.extend(payload.as_bytes());
+            if write_half.write_all(&packet).await.is_err() {
+                reader.abort();
+                return;
+            }
+            if opcode == OPCODES::Close {
+                reader.abort();
+                return;
+            }
+        }
+    }
+
+    reader.abort();
+}
+
+/// Works out the frames to send back for a received frame.
+fn reply_to(frame: &ReceivedFrame, state: &Mutex<State>) -> Vec<(OPCODES, String)> {
+    let state = state.lock().unwrap();
+
+    if frame.opcode == OPCODES::Handshake as u32 {
+        if let Some((code, message)) = &state.reject_handshake {
+            let close = json!({ "code": code, "message": message });
+            return vec![(OPCODES::Close, close.to_string())];
+        }
+
+        let data = state.ready_data.clone().unwrap_or_else(default_ready_data);
+        let ready = json!({ "cmd": "DISPATCH", "evt": "READY", "data": data, "nonce": null });
+        return vec![(OPCODES::Frame, ready.to_string())];
+    }
+
+    if frame.opcode != OPCODES::Frame as u32 {
+        return Vec::new();
+    }
+
+    let command = frame.json();
+    let cmd = command["cmd"].as_str().unwrap_or_default().to_string();
+    let nonce = command["nonce"].clone();
+
+    let response = match state.responses.get(&cmd) {
+        Some(MockResponse::Data(data)) => {
+            json!({ "cmd": cmd, "data": data, "evt": null, "nonce": nonce })
+        }
+        Some(MockResponse::Error { code, message }) => json!({
+            "cmd": cmd,
+            "data": { "code": code, "message": message },
+            "evt": "ERROR",
+            "nonce": nonce
+        }),
+        Some(MockResponse::Ignore) => return Vec::new(),
+        None => {
+            let data = match cmd.as_str() {
+                "SUBSCRIBE" | "UNSUBSCRIBE" => json!({ "evt": command["evt"] }),
+                _ => command.get("args").cloned().unwrap_or(Value::Null),
+            };
+            json!({ "cmd": cmd, "data": data, "evt": null, "nonce": nonce })
+        }
+    };
+
+    vec![(OPCODES::Frame, response.to_string())]
+}
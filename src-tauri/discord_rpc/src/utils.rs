@@ -1,3 +1,4 @@
+use crate::errors::DiscordRPCError;
 use crate::Result;
 use serde_json::Value;
 use std::convert::TryInto;
@@ -16,10 +17,12 @@ pub fn nonce() -> String {
 }
 
 pub fn create_json(value: &mut serde_json::Value) -> Result<String> {
-    let uuid = nonce();
+    create_json_with_nonce(value, nonce())
+}
 
+pub fn create_json_with_nonce(value: &mut serde_json::Value, nonce: String) -> Result<String> {
     let payload = value.as_object_mut().expect("payload must be an object");
-    payload.insert("nonce".to_string(), Value::String(uuid));
+    payload.insert("nonce".to_string(), Value::String(nonce));
 
     // TODO: handle error
     Ok(serde_json::to_string(&payload)?)
@@ -64,18 +67,67 @@ pub fn temp_directory() -> PathBuf {
     PathBuf::from(path)
 }
 
-/// iterate over 0-10 index and check if files exists then return the path
-pub fn get_pipe_pattern() -> PathBuf {
-    for i in 0..10 {
-        #[cfg(target_os = "windows")]
-        let path = format!(r"\\?\pipe\discord-ipc-{}", i);
+/// Environment variable that points directly at the ipc socket to use,
+/// bypassing discovery.
+pub const IPC_PATH_ENV: &str = "DISCORD_IPC_PATH";
+
+/// Sub directories of the temp dir used by sandboxed Discord installs
+/// (Flatpak and Snap respectively).
+#[cfg(target_family = "unix")]
+const SANDBOX_DIRS: [&str; 2] = ["app/com.discordapp.Discord", "snap.discord"];
+
+/// returns every path that may hold an ipc socket, in the order they are checked
+pub fn pipe_candidates() -> Vec<PathBuf> {
+    #[cfg(target_os = "windows")]
+    let dirs = vec![PathBuf::from(r"\\?\pipe")];
+
+    #[cfg(target_family = "unix")]
+    let dirs = {
+        let temp = temp_directory();
+        let mut dirs = vec![temp.clone()];
+        dirs.extend(SANDBOX_DIRS.iter().map(|dir| temp.join(dir)));
+        dirs
+    };
+
+    dirs.iter()
+        .flat_map(|dir| (0..10).map(move |i| dir.join(format!("discord-ipc-{}", i))))
+        .collect()
+}
 
-        #[cfg(target_family = "unix")]
-        let path = temp_directory().join(format!("discord-ipc-{}", i));
+/// returns the path of the first ipc socket that exists
+///
+/// An explicit `path_override`, or otherwise the `DISCORD_IPC_PATH`
+/// environment variable, is used as-is instead of searching.
+pub fn find_pipe(path_override: Option<&Path>) -> Result<PathBuf> {
+    Ok(find_pipes(path_override)?.remove(0))
+}
 
-        if Path::new(&path).exists() {
-            return Path::new(&path).to_path_buf();
-        }
+/// returns the paths of every ipc socket that exists, in discovery order
+///
+/// Every running Discord client (Stable, PTB, Canary) binds its own socket.
+/// The override rules of [`find_pipe`] apply.
+pub fn find_pipes(path_override: Option<&Path>) -> Result<Vec<PathBuf>> {
+    let path_override = path_override
+        .map(Path::to_path_buf)
+        .or_else(|| var(IPC_PATH_ENV).ok().map(PathBuf::from));
+
+    let candidates = match path_override {
+        Some(path) => vec![path],
+        None => pipe_candidates(),
+    };
+
+    let pipes: Vec<PathBuf> = candidates
+        .iter()
+        .filter(|path| path.exists())
+        .cloned()
+        .collect();
+    if pipes.is_empty() {
+        return Err(DiscordRPCError::PipeNotFound(candidates));
     }
-    panic!("Could not find discord-ipc-0");
+    Ok(pipes)
+}
+
+/// returns the path of the first ipc socket that exists, see [`find_pipe`]
+pub fn get_pipe_pattern() -> Result<PathBuf> {
+    find_pipe(None)
 }
@@ -0,0 +1,135 @@
+#![cfg(all(unix, feature = "blocking"))]
+
+use discord_rpc::blocking::DiscordIPCClient;
+use discord_rpc::errors::DiscordRPCError;
+use discord_rpc::models::commands::{Activity, SetActivityArgs};
+use discord_rpc::models::events::EventPayload;
+use discord_rpc::models::rpc_command::RPCCommand;
+use discord_rpc::models::rpc_event::RPCEvent;
+use discord_rpc::testing::MockServer;
+use serde_json::json;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const CLIENT_ID: &str = "1051728796149096458";
+
+fn connect(path: PathBuf) -> DiscordIPCClient {
+    DiscordIPCClient::builder(CLIENT_ID)
+        .socket_path(path)
+        .timeout(Duration::from_secs(5))
+        .connect()
+        .expect("client should connect to the mock server")
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn blocking_client_sets_activity() {
+    let server = MockServer::start().await.unwrap();
+    let path = server.path().to_path_buf();
+
+    let username = tokio::task::spawn_blocking(move || {
+        let mut client = connect(path);
+        let activity = Activity::new().details("Frieren".to_string());
+        client
+            .request(&RPCCommand::SetActivity(SetActivityArgs::new(activity)))
+            .unwrap();
+        let username = client.user().username.clone();
+        client.close().unwrap();
+        username
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(username, "mock");
+    let commands = server.commands_named("SET_ACTIVITY");
+    assert_eq!(commands.len(), 2);
+    assert_eq!(commands[0]["args"]["activity"]["details"], "Frieren");
+    assert!(commands[1]["args"]["activity"].is_null());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn blocking_client_receives_events() {
+    let server = MockServer::start().await.unwrap();
+    let path = server.path().to_path_buf();
+
+    let client = tokio::task::spawn_blocking(move || {
+        let mut client = connect(path);
+        let event = RPCEvent::SpeakingStart {
+            channel_id: "1".to_string(),
+        };
+        client.request(&RPCCommand::Subscribe(event)).unwrap();
+        client.next_event()
+    });
+
+    server.wait_for_commands("SUBSCRIBE", 1).await;
+    server.send_event(
+        "SPEAKING_START",
+        json!({ "channel_id": "1", "user_id": "3" }),
+    );
+
+    match client.await.unwrap() {
+        Ok(EventPayload::SpeakingStart { data }) => assert_eq!(data.user_id, "3"),
+        other => panic!("expected SPEAKING_START, got {:?}", other),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn blocking_client_reports_rejected_handshake() {
+    let server = MockServer::start().await.unwrap();
+    server.reject_handshake(4000, "Invalid Client ID");
+    let path = server.path().to_path_buf();
+
+    let result = tokio::task::spawn_blocking(move || {
+        DiscordIPCClient::builder(CLIENT_ID)
+            .socket_path(path)
+            .connect()
+            .map(|_| ())
+    })
+    .await
+    .unwrap();
+
+    match result {
+        Err(DiscordRPCError::HandshakeFailed { code, .. }) => assert_eq!(code, 4000),
+        other => panic!("expected a handshake error, got {:?}", other),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn blocking_client_fails_after_oversized_frame() {
+    let server = MockServer::start().await.unwrap();
+    let path = server.path().to_path_buf();
+
+    let client = tokio::task::spawn_blocking(move || {
+        let mut client = DiscordIPCClient::builder(CLIENT_ID)
+            .socket_path(path)
+            .timeout(Duration::from_secs(5))
+            .max_payload(1024)
+            .connect()
+            .unwrap();
+        let event = RPCEvent::SpeakingStart {
+            channel_id: "1".to_string(),
+        };
+        client.request(&RPCCommand::Subscribe(event)).unwrap();
+        let first = client.next_event().map(|_| ());
+        let activity = RPCCommand::SetActivity(SetActivityArgs::default());
+        (first, client.request(&activity).map(|_| ()))
+    });
+
+    server.wait_for_commands("SUBSCRIBE", 1).await;
+    server.send_event(
+        "SPEAKING_START",
+        json!({ "channel_id": "1", "user_id": "3".repeat(2048) }),
+    );
+
+    let (first, second) = client.await.unwrap();
+    assert!(
+        matches!(first, Err(DiscordRPCError::FrameTooLarge { .. })),
+        "expected FrameTooLarge, got {:?}",
+        first
+    );
+    assert!(
+        matches!(second, Err(DiscordRPCError::ConnectionLost)),
+        "expected ConnectionLost, got {:?}",
+        second
+    );
+    assert!(server.commands_named("SET_ACTIVITY").is_empty());
+}
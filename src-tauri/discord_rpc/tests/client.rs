@@ -0,0 +1,1104 @@
+#![cfg(unix)]
+
+use discord_rpc::errors::{DiscordRPCError, RpcErrorCode};
+use discord_rpc::models::commands::{
+    Activity, ActivityAssets, ActivityButton, ActivityParty, ActivitySecrets, ActivityType,
+    GetChannelsArgs, GetImageArgs, ImageFormat, OAuthScope, SelectVoiceChannelArgs,
+    SetActivityArgs, SetVoiceSettingsArgs, SetVoiceSettingsIo, StatusDisplayType, TokenResponse,
+    VoiceModeType,
+};
+use discord_rpc::models::events::EventPayload;
+use discord_rpc::models::rpc_command::RPCCommand;
+use discord_rpc::models::rpc_event::RPCEvent;
+use discord_rpc::opcodes::OPCODES;
+use discord_rpc::testing::{MockResponse, MockServer};
+use discord_rpc::{Backoff, ConnectionState, DiscordIPCClient, EventReceive, RateLimit};
+use futures::StreamExt;
+use serde_json::json;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+const CLIENT_ID: &str = "1051728796149096458";
+
+async fn connect(server: &MockServer) -> DiscordIPCClient {
+    DiscordIPCClient::builder(CLIENT_ID)
+        .socket_path(server.path())
+        .connect()
+        .await
+        .expect("client should connect to the mock server")
+}
+
+/// Waits until the client's state matches `condition`.
+async fn wait_for_state<F>(
+    states: &mut watch::Receiver<ConnectionState>,
+    condition: F,
+) -> ConnectionState
+where
+    F: Fn(&ConnectionState) -> bool,
+{
+    tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let state = states.borrow_and_update().clone();
+            if condition(&state) {
+                return state;
+            }
+            states.changed().await.unwrap();
+        }
+    })
+    .await
+    .expect("the client did not reach the expected state")
+}
+
+fn set_activity(details: &str) -> RPCCommand {
+    RPCCommand::SetActivity(SetActivityArgs::new(
+        Activity::new().details(details.to_string()),
+    ))
+}
+
+#[tokio::test]
+async fn handshake_sends_client_id() {
+    let server = MockServer::start().await.unwrap();
+    let _client = connect(&server).await;
+
+    let handshakes = server.handshakes();
+    assert_eq!(handshakes.len(), 1);
+    assert_eq!(handshakes[0], json!({ "v": 1, "client_id": CLIENT_ID }));
+}
+
+#[tokio::test]
+async fn ready_data_is_exposed() {
+    let server = MockServer::start().await.unwrap();
+    let client = connect(&server).await;
+
+    assert_eq!(client.user().username, "mock");
+    assert_eq!(client.config().environment, "production");
+    assert_eq!(
+        client.avatar_url(),
+        "https://cdn.discordapp.com/avatars/123456789012345678/a_0123456789abcdef.gif"
+    );
+}
+
+#[tokio::test]
+async fn rejected_handshake_is_a_handshake_error() {
+    let server = MockServer::start().await.unwrap();
+    server.reject_handshake(4000, "Invalid Client ID");
+
+    let result = DiscordIPCClient::builder(CLIENT_ID)
+        .socket_path(server.path())
+        .connect()
+        .await;
+
+    match result {
+        Err(DiscordRPCError::HandshakeFailed { code, message }) => {
+            assert_eq!(code, 4000);
+            assert_eq!(message, "Invalid Client ID");
+        }
+        other => panic!("expected a handshake error, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[tokio::test]
+async fn set_activity_sends_command_with_nonce() {
+    let server = MockServer::start().await.unwrap();
+    let client = connect(&server).await;
+
+    client.request(&set_activity("Frieren")).await.unwrap();
+
+    let commands = server.commands_named("SET_ACTIVITY");
+    assert_eq!(commands.len(), 1);
+    assert_eq!(commands[0]["args"]["activity"]["details"], "Frieren");
+    assert_eq!(commands[0]["args"]["pid"], std::process::id());
+    assert!(commands[0]["nonce"].is_string());
+}
+
+#[tokio::test]
+async fn activity_type_and_urls_are_sent() {
+    let server = MockServer::start().await.unwrap();
+    let client = connect(&server).await;
+
+    let activity = Activity::new()
+        .activity_type(ActivityType::Watching)
+        .status_display_type(StatusDisplayType::Details)
+        .details("Frieren".to_string())
+        .details_url("https://anidex.app/frieren".to_string());
+    client
+        .request(&RPCCommand::SetActivity(SetActivityArgs::new(activity)))
+        .await
+        .unwrap();
+
+    let activity = &server.commands_named("SET_ACTIVITY")[0]["args"]["activity"];
+    assert_eq!(activity["type"], 3);
+    assert_eq!(activity["status_display_type"], 2);
+    assert_eq!(activity["details_url"], "https://anidex.app/frieren");
+    assert!(activity.get("state_url").is_none());
+}
+
+#[tokio::test]
+async fn error_response_fails_the_request() {
+    let server = MockServer::start().await.unwrap();
+    server.respond_to(
+        "SET_ACTIVITY",
+        MockResponse::Error {
+            code: 4000,
+            message: "child \"activity\" fails".to_string(),
+        },
+    );
+    let client = connect(&server).await;
+
+    match client.request(&set_activity("Frieren")).await {
+        Err(DiscordRPCError::Rpc {
+            code,
+            message,
+            nonce,
+        }) => {
+            assert_eq!(code, RpcErrorCode::InvalidPayload);
+            assert_eq!(message, "child \"activity\" fails");
+            assert_eq!(
+                nonce.as_deref(),
+                server.commands_named("SET_ACTIVITY")[0]["nonce"].as_str()
+            );
+        }
+        other => panic!("expected an rpc error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn invalid_activity_is_rejected_before_sending() {
+    let server = MockServer::start().await.unwrap();
+    let client = connect(&server).await;
+
+    let activity = Activity::new()
+        .details("Frieren".to_string())
+        .state("1".to_string())
+        .party(ActivityParty::new().id("party".to_string()).size((3, 2)));
+    let command = RPCCommand::SetActivity(SetActivityArgs::new(activity));
+
+    match client.request(&command).await {
+        Err(DiscordRPCError::InvalidActivity(fields)) => {
+            let fields: Vec<_> = fields.iter().map(|f| f.field.as_str()).collect();
+            assert_eq!(fields, ["state", "party.size"]);
+        }
+        other => panic!("expected an invalid activity error, got {:?}", other),
+    }
+    assert!(server.commands_named("SET_ACTIVITY").is_empty());
+}
+
+#[test]
+fn activity_parts_validate_on_their_own() {
+    let button = ActivityButton::new().label("Play".to_string());
+    match button.validate() {
+        Err(DiscordRPCError::InvalidActivity(fields)) => {
+            let fields: Vec<_> = fields.iter().map(|f| f.field.as_str()).collect();
+            assert_eq!(fields, ["url"]);
+        }
+        other => panic!("expected an invalid button, got {:?}", other),
+    }
+
+    let assets = ActivityAssets::new().large_text("Frieren".to_string());
+    assert!(assets.validate().is_ok());
+}
+
+#[tokio::test]
+async fn ping_is_answered_with_pong() {
+    let server = MockServer::start().await.unwrap();
+    let _client = connect(&server).await;
+
+    server.send_frame(OPCODES::Ping, "{\"ping\":1}");
+
+    server
+        .wait_until(|server| {
+            server
+                .frames()
+                .iter()
+                .any(|frame| frame.opcode == OPCODES::Pong as u32)
+        })
+        .await;
+    let pong = server
+        .frames()
+        .into_iter()
+        .find(|frame| frame.opcode == OPCODES::Pong as u32)
+        .unwrap();
+    assert_eq!(pong.payload, "{\"ping\":1}");
+}
+
+#[tokio::test]
+async fn reconnects_and_replays_activity() {
+    let server = MockServer::start().await.unwrap();
+    let client = connect(&server).await;
+
+    client.request(&set_activity("Frieren")).await.unwrap();
+    server.disconnect();
+
+    // the reader notices the dropped socket, reconnects and replays the activity
+    let commands = server.wait_for_commands("SET_ACTIVITY", 2).await;
+    assert_eq!(server.handshakes().len(), 2);
+    assert_eq!(commands[1]["args"]["activity"]["details"], "Frieren");
+
+    client.request(&set_activity("Frieren - 2")).await.unwrap();
+}
+
+#[tokio::test]
+async fn requests_survive_a_write_to_a_dropped_socket() {
+    let server = MockServer::start().await.unwrap();
+    server.respond_to("GET_GUILDS", MockResponse::Data(json!({ "guilds": [] })));
+    let client = connect(&server).await;
+    client.request(&set_activity("Frieren")).await.unwrap();
+
+    // the write fails, the client reconnects and sends the request again
+    server.stop_reading().await;
+    let data = client.get_guilds().await.unwrap();
+    assert!(data.guilds.is_empty());
+    assert_eq!(server.handshakes().len(), 2);
+    assert_eq!(server.commands_named("GET_GUILDS").len(), 1);
+
+    // the reconnect replays the new activity, which answers the request
+    server.stop_reading().await;
+    client.request(&set_activity("Frieren - 2")).await.unwrap();
+    assert_eq!(server.handshakes().len(), 3);
+    let details: Vec<_> = server
+        .commands_named("SET_ACTIVITY")
+        .iter()
+        .map(|command| command["args"]["activity"]["details"].clone())
+        .collect();
+    assert_eq!(
+        details,
+        [json!("Frieren"), json!("Frieren"), json!("Frieren - 2")]
+    );
+}
+
+#[tokio::test]
+async fn unanswered_requests_time_out() {
+    let server = MockServer::start().await.unwrap();
+    server.respond_to("GET_GUILDS", MockResponse::Ignore);
+    let client = DiscordIPCClient::builder(CLIENT_ID)
+        .socket_path(server.path())
+        .request_timeout(Duration::from_millis(100))
+        .connect()
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        client.get_guilds().await,
+        Err(DiscordRPCError::Timeout)
+    ));
+    // the client is still usable
+    client.request(&set_activity("Frieren")).await.unwrap();
+}
+
+#[tokio::test]
+async fn connection_state_follows_reconnects_and_close() {
+    let server = MockServer::start().await.unwrap();
+    let client = DiscordIPCClient::builder(CLIENT_ID)
+        .socket_path(server.path())
+        .backoff(Backoff::new(
+            Duration::from_millis(10),
+            Duration::from_millis(50),
+        ))
+        .connect()
+        .await
+        .unwrap();
+    let mut states = client.connection_state();
+    match &*states.borrow() {
+        ConnectionState::Ready { user } => assert_eq!(user.username, "mock"),
+        other => panic!("expected a ready client, got {:?}", other),
+    }
+
+    server.reject_handshake(4000, "Invalid Client ID");
+    server.disconnect();
+    let state = wait_for_state(
+        &mut states,
+        |state| matches!(state, ConnectionState::Reconnecting { attempts, .. } if *attempts > 0),
+    )
+    .await;
+    match state {
+        ConnectionState::Reconnecting { reason, .. } => {
+            assert!(reason.contains("Invalid Client ID"))
+        }
+        other => panic!("expected a reconnecting client, got {:?}", other),
+    }
+
+    server.accept_handshake();
+    wait_for_state(&mut states, ConnectionState::is_ready).await;
+
+    client.close().await.unwrap();
+    assert_eq!(
+        *states.borrow(),
+        ConnectionState::Closed {
+            reason: "closed by the client".to_string()
+        }
+    );
+}
+
+#[tokio::test]
+async fn client_closes_once_the_retries_run_out() {
+    let server = MockServer::start().await.unwrap();
+    server.respond_to("GET_GUILDS", MockResponse::Ignore);
+    let client = DiscordIPCClient::builder(CLIENT_ID)
+        .socket_path(server.path())
+        .backoff(
+            Backoff::new(Duration::from_millis(10), Duration::from_millis(20)).max_retries(Some(2)),
+        )
+        .connect()
+        .await
+        .unwrap();
+    let mut states = client.connection_state();
+
+    server.reject_handshake(4000, "Invalid Client ID");
+    let (result, _) = tokio::join!(client.request(&RPCCommand::GetGuilds), async {
+        server.wait_for_commands("GET_GUILDS", 1).await;
+        server.disconnect();
+    });
+    assert!(matches!(result, Err(DiscordRPCError::ConnectionLost)));
+
+    match wait_for_state(&mut states, |state| {
+        matches!(state, ConnectionState::Closed { .. })
+    })
+    .await
+    {
+        ConnectionState::Closed { reason } => assert!(reason.contains("Invalid Client ID")),
+        other => panic!("expected a closed client, got {:?}", other),
+    }
+    // the first handshake, then one attempt and two retries
+    assert_eq!(server.handshakes().len(), 4);
+    assert!(matches!(
+        client.request(&RPCCommand::GetGuilds).await,
+        Err(DiscordRPCError::ClientClosed)
+    ));
+}
+
+#[tokio::test]
+async fn subscription_streams_events_and_unsubscribes_on_drop() {
+    let server = MockServer::start().await.unwrap();
+    let client = connect(&server).await;
+
+    let event = RPCEvent::SpeakingStart {
+        channel_id: "1".to_string(),
+    };
+    let mut stream = client.subscribe(event).await.unwrap();
+    assert_eq!(server.commands_named("SUBSCRIBE").len(), 1);
+
+    server.send_event(
+        "SPEAKING_STOP",
+        json!({ "channel_id": "1", "user_id": "2" }),
+    );
+    server.send_event(
+        "SPEAKING_START",
+        json!({ "channel_id": "1", "user_id": "3" }),
+    );
+
+    match stream.next().await {
+        Some(EventPayload::SpeakingStart { data }) => assert_eq!(data.user_id, "3"),
+        other => panic!("expected SPEAKING_START, got {:?}", other),
+    }
+
+    drop(stream);
+    let unsubscribes = server.wait_for_commands("UNSUBSCRIBE", 1).await;
+    assert_eq!(unsubscribes[0]["args"]["channel_id"], "1");
+}
+
+#[tokio::test]
+async fn streams_only_receive_their_own_channel() {
+    let server = MockServer::start().await.unwrap();
+    let client = connect(&server).await;
+
+    let speaking_in = |channel_id: &str| RPCEvent::SpeakingStart {
+        channel_id: channel_id.to_string(),
+    };
+    let mut first = client.subscribe(speaking_in("1")).await.unwrap();
+    let mut second = client.subscribe(speaking_in("2")).await.unwrap();
+
+    server.send_event(
+        "SPEAKING_START",
+        json!({ "channel_id": "2", "user_id": "4" }),
+    );
+    server.send_event(
+        "SPEAKING_START",
+        json!({ "channel_id": "1", "user_id": "3" }),
+    );
+
+    match first.next().await {
+        Some(EventPayload::SpeakingStart { data }) => assert_eq!(data.user_id, "3"),
+        other => panic!("expected SPEAKING_START in 1, got {:?}", other),
+    }
+    match second.next().await {
+        Some(EventPayload::SpeakingStart { data }) => assert_eq!(data.user_id, "4"),
+        other => panic!("expected SPEAKING_START in 2, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn resubscribing_after_dropping_a_stream_stays_subscribed() {
+    let server = MockServer::start().await.unwrap();
+    let client = connect(&server).await;
+
+    let event = RPCEvent::SpeakingStart {
+        channel_id: "1".to_string(),
+    };
+    let stream = client.subscribe(event.clone()).await.unwrap();
+    drop(stream);
+    let mut stream = client.subscribe(event.clone()).await.unwrap();
+
+    // the unsubscribe of the dropped stream must not come last
+    let order: Vec<_> = server
+        .commands()
+        .iter()
+        .map(|command| command["cmd"].as_str().unwrap().to_string())
+        .filter(|cmd| cmd.ends_with("SUBSCRIBE"))
+        .collect();
+    assert_eq!(order, ["SUBSCRIBE", "UNSUBSCRIBE", "SUBSCRIBE"]);
+    assert_eq!(client.subscriptions(), vec![event]);
+
+    server.send_event(
+        "SPEAKING_START",
+        json!({ "channel_id": "1", "user_id": "3" }),
+    );
+    match stream.next().await {
+        Some(EventPayload::SpeakingStart { data }) => assert_eq!(data.user_id, "3"),
+        other => panic!("expected SPEAKING_START, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn unsubscribe_ends_streams_without_touching_a_new_subscription() {
+    let server = MockServer::start().await.unwrap();
+    let client = connect(&server).await;
+
+    let event = RPCEvent::SpeakingStart {
+        channel_id: "1".to_string(),
+    };
+    let mut old = client.subscribe(event.clone()).await.unwrap();
+    client.unsubscribe(event.clone()).await.unwrap();
+    assert!(old.next().await.is_none());
+
+    let mut new = client.subscribe(event.clone()).await.unwrap();
+    drop(old);
+    assert_eq!(client.subscriptions(), vec![event.clone()]);
+
+    server.send_event(
+        "SPEAKING_START",
+        json!({ "channel_id": "1", "user_id": "3" }),
+    );
+    match new.next().await {
+        Some(EventPayload::SpeakingStart { data }) => assert_eq!(data.user_id, "3"),
+        other => panic!("expected SPEAKING_START, got {:?}", other),
+    }
+    assert_eq!(server.commands_named("UNSUBSCRIBE").len(), 1);
+
+    // the new subscription is the one restored after a reconnect
+    server.disconnect();
+    let subscribes = server.wait_for_commands("SUBSCRIBE", 3).await;
+    assert_eq!(subscribes[2]["args"]["channel_id"], "1");
+}
+
+#[tokio::test]
+async fn subscriptions_are_restored_after_reconnect() {
+    let server = MockServer::start().await.unwrap();
+    let client = connect(&server).await;
+
+    let event = RPCEvent::SpeakingStart {
+        channel_id: "1".to_string(),
+    };
+    let mut stream = client.subscribe(event.clone()).await.unwrap();
+    assert_eq!(client.subscriptions(), vec![event.clone()]);
+
+    server.disconnect();
+    let subscribes = server.wait_for_commands("SUBSCRIBE", 2).await;
+    assert_eq!(server.handshakes().len(), 2);
+    assert_eq!(subscribes[1]["evt"], "SPEAKING_START");
+    assert_eq!(subscribes[1]["args"]["channel_id"], "1");
+
+    // the stream keeps receiving events from the new connection
+    server.send_event(
+        "SPEAKING_START",
+        json!({ "channel_id": "1", "user_id": "3" }),
+    );
+    match stream.next().await {
+        Some(EventPayload::SpeakingStart { data }) => assert_eq!(data.user_id, "3"),
+        other => panic!("expected SPEAKING_START, got {:?}", other),
+    }
+
+    client.unsubscribe(event).await.unwrap();
+    assert!(client.subscriptions().is_empty());
+    let unsubscribes = server.commands_named("UNSUBSCRIBE");
+    assert_eq!(unsubscribes[0]["args"]["channel_id"], "1");
+}
+
+#[tokio::test]
+async fn handler_reports_errors_and_stops_with_the_client() {
+    let server = MockServer::start().await.unwrap();
+    let client = connect(&server).await;
+
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let handler = client.handler(move |event| {
+        let _ = sender.send(event);
+    });
+
+    server.send_frame(OPCODES::Frame, "not json");
+    match receiver.recv().await {
+        Some(Err(e)) => assert!(matches!(*e, DiscordRPCError::SerdeJson(_))),
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+
+    // nobody awaits an emitted command, so its error goes to the handler
+    server.respond_to(
+        "SET_ACTIVITY",
+        MockResponse::Error {
+            code: 4000,
+            message: "bad activity".to_string(),
+        },
+    );
+    let activity = Activity::new().details("Frieren".to_string());
+    client
+        .emit_command(&RPCCommand::SetActivity(SetActivityArgs::new(activity)))
+        .await
+        .unwrap();
+    match receiver.recv().await {
+        Some(Err(e)) => assert!(matches!(
+            *e,
+            DiscordRPCError::Rpc {
+                code: RpcErrorCode::InvalidPayload,
+                ..
+            }
+        )),
+        other => panic!("expected an rpc error, got {:?}", other),
+    }
+    server.respond_to("SET_ACTIVITY", MockResponse::Data(json!({})));
+
+    // the handler learns why the connection ended
+    let mut states = client.connection_state();
+    server.send_frame(OPCODES::Close, r#"{"code":1000,"message":"bye"}"#);
+    match receiver.recv().await {
+        Some(Err(e)) => assert!(matches!(*e, DiscordRPCError::Closed { code: 1000, .. })),
+        other => panic!("expected the close reason, got {:?}", other),
+    }
+    wait_for_state(&mut states, ConnectionState::is_ready).await;
+
+    server.disconnect();
+    // the activity replayed after the reconnect is answered first
+    let error = loop {
+        match receiver.recv().await {
+            Some(Ok(_)) => continue,
+            other => break other,
+        }
+    };
+    match error {
+        Some(Err(e)) => assert!(matches!(*e, DiscordRPCError::Io(_))),
+        other => panic!("expected the lost socket, got {:?}", other),
+    }
+    wait_for_state(&mut states, ConnectionState::is_ready).await;
+    client.close().await.unwrap();
+
+    // the handler ends with the client, dropping its sender
+    while let Some(event) = receiver.recv().await {
+        assert!(event.is_ok(), "unexpected error {:?}", event);
+    }
+    while !handler.is_finished() {
+        tokio::task::yield_now().await;
+    }
+}
+
+#[tokio::test]
+async fn message_events_are_typed() {
+    let server = MockServer::start().await.unwrap();
+    let client = connect(&server).await;
+
+    let mut messages = client
+        .subscribe(RPCEvent::MessageCreate {
+            channel_id: "1".to_string(),
+        })
+        .await
+        .unwrap();
+    server.send_event(
+        "MESSAGE_CREATE",
+        json!({
+            "channel_id": "1",
+            "message": {
+                "id": "5",
+                "content": "next episode?",
+                "author": {
+                    "id": "3",
+                    "username": "friend",
+                    "discriminator": "0",
+                    "avatar": null
+                },
+                "type": 0
+            }
+        }),
+    );
+
+    match messages.next().await {
+        Some(EventPayload::MessageCreate { data }) => {
+            assert_eq!(data.message.content, "next episode?");
+            assert_eq!(data.message.author.unwrap().username, "friend");
+        }
+        other => panic!("expected MESSAGE_CREATE, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn unknown_events_reach_the_handler() {
+    let server = MockServer::start().await.unwrap();
+    let client = connect(&server).await;
+
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let _handler = client.handler(move |event| {
+        let _ = sender.send(event);
+    });
+    server.send_event("SOMETHING_NEW", json!({ "answer": 42 }));
+
+    match receiver.recv().await {
+        Some(Ok(EventReceive::Event(EventPayload::Unknown { evt, raw }))) => {
+            assert_eq!(evt, "SOMETHING_NEW");
+            assert_eq!(raw["data"]["answer"], 42);
+        }
+        other => panic!("expected an unknown event, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn known_events_that_do_not_decode_are_reported() {
+    let server = MockServer::start().await.unwrap();
+    let client = connect(&server).await;
+
+    let mut stream = client
+        .subscribe(RPCEvent::SpeakingStart {
+            channel_id: "1".to_string(),
+        })
+        .await
+        .unwrap();
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let _handler = client.handler(move |event| {
+        let _ = sender.send(event);
+    });
+
+    // no user_id, so this is not a SPEAKING_START this crate can type
+    server.send_event("SPEAKING_START", json!({ "channel_id": "1" }));
+    server.send_event(
+        "SPEAKING_START",
+        json!({ "channel_id": "1", "user_id": "3" }),
+    );
+
+    match stream.next().await {
+        Some(EventPayload::SpeakingStart { data }) => assert_eq!(data.user_id, "3"),
+        other => panic!("expected the valid SPEAKING_START, got {:?}", other),
+    }
+    // the stream reports the frame it skipped to the handlers
+    let error = loop {
+        match receiver.recv().await {
+            Some(Ok(_)) => continue,
+            other => break other,
+        }
+    };
+    match error {
+        Some(Err(e)) => assert!(matches!(*e, DiscordRPCError::SerdeJson(_))),
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn login_runs_the_oauth_flow() {
+    let server = MockServer::start().await.unwrap();
+    server.respond_to(
+        "AUTHORIZE",
+        MockResponse::Data(json!({ "code": "the-code" })),
+    );
+    server.respond_to(
+        "AUTHENTICATE",
+        MockResponse::Data(json!({
+            "application": {
+                "description": "",
+                "icon": null,
+                "id": CLIENT_ID,
+                "name": "Anidex"
+            },
+            "expires": "2026-10-25T00:00:00.000Z",
+            "user": {
+                "username": "mock",
+                "discriminator": "0001",
+                "id": "123456789012345678",
+                "avatar": null
+            },
+            "scopes": ["rpc", "identify"]
+        })),
+    );
+    let client = connect(&server).await;
+
+    let auth = client
+        .login(
+            vec![OAuthScope::Rpc, OAuthScope::Identify],
+            |request| async move {
+                assert_eq!(request.code, "the-code");
+                assert_eq!(request.grant_type, "authorization_code");
+                Ok(TokenResponse {
+                    access_token: "the-token".to_string(),
+                    token_type: "Bearer".to_string(),
+                    expires_in: 604800,
+                    refresh_token: None,
+                    scope: "rpc identify".to_string(),
+                })
+            },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(auth.user.username, "mock");
+    assert_eq!(
+        server.commands_named("AUTHORIZE")[0]["args"]["scopes"],
+        json!(["rpc", "identify"])
+    );
+    assert_eq!(
+        server.commands_named("AUTHENTICATE")[0]["args"]["access_token"],
+        "the-token"
+    );
+}
+
+#[tokio::test]
+async fn voice_state_events_are_typed() {
+    let server = MockServer::start().await.unwrap();
+    let client = connect(&server).await;
+
+    let mut joins = client
+        .subscribe(RPCEvent::VoiceStateCreate {
+            channel_id: "1".to_string(),
+        })
+        .await
+        .unwrap();
+    let mut selects = client
+        .subscribe(RPCEvent::VoiceChannelSelect)
+        .await
+        .unwrap();
+    assert_eq!(
+        server.commands_named("SUBSCRIBE")[0]["args"]["channel_id"],
+        "1"
+    );
+
+    server.send_event(
+        "VOICE_STATE_CREATE",
+        json!({
+            "voice_state": {
+                "mute": false,
+                "deaf": false,
+                "self_mute": true,
+                "self_deaf": false,
+                "suppress": false
+            },
+            "user": {
+                "id": "190320984123768832",
+                "username": "friend",
+                "discriminator": "7479",
+                "avatar": null,
+                "bot": false
+            },
+            "nick": "friend",
+            "volume": 100,
+            "mute": false,
+            "pan": { "left": 1.0, "right": 1.0 }
+        }),
+    );
+    server.send_event(
+        "VOICE_CHANNEL_SELECT",
+        json!({ "channel_id": null, "guild_id": null }),
+    );
+
+    match joins.next().await {
+        Some(EventPayload::VoiceStateCreate { data }) => {
+            assert!(data.state.self_mute);
+            let user = data.user.expect("voice state should carry the user");
+            assert_eq!(user.username, "friend");
+            assert_eq!(user.avatar, None);
+        }
+        other => panic!("expected VOICE_STATE_CREATE, got {:?}", other),
+    }
+    match selects.next().await {
+        Some(EventPayload::VoiceChannelSelect { data }) => assert_eq!(data.channel_id, None),
+        other => panic!("expected VOICE_CHANNEL_SELECT, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn guild_and_channel_queries_are_typed() {
+    let server = MockServer::start().await.unwrap();
+    server.respond_to(
+        "GET_GUILDS",
+        MockResponse::Data(json!({
+            "guilds": [{ "id": "10", "name": "Anime club", "icon_url": null }]
+        })),
+    );
+    server.respond_to(
+        "GET_CHANNELS",
+        MockResponse::Data(json!({
+            "channels": [{ "id": "20", "name": "watch-party", "type": 0 }]
+        })),
+    );
+    server.respond_to("SELECT_VOICE_CHANNEL", MockResponse::Data(json!(null)));
+    let client = connect(&server).await;
+
+    let data = client.get_guilds().await.unwrap();
+    assert_eq!(data.guilds[0].name, "Anime club");
+    assert_eq!(server.commands_named("GET_GUILDS")[0]["args"], json!({}));
+
+    let data = client
+        .get_channels(GetChannelsArgs::new("10"))
+        .await
+        .unwrap();
+    assert_eq!(data.channels[0].name, "watch-party");
+    assert_eq!(
+        server.commands_named("GET_CHANNELS")[0]["args"],
+        json!({ "guild_id": "10" })
+    );
+
+    let left = client
+        .select_voice_channel(SelectVoiceChannelArgs::new(None).force(true))
+        .await
+        .unwrap();
+    assert!(left.is_none());
+    assert_eq!(
+        server.commands_named("SELECT_VOICE_CHANNEL")[0]["args"],
+        json!({ "channel_id": null, "force": true })
+    );
+}
+
+#[tokio::test]
+async fn voice_settings_can_be_changed_and_restored() {
+    let settings = json!({
+        "input": {
+            "device_id": "default",
+            "volume": 100,
+            "available_devices": [{ "id": "default", "name": "Default" }]
+        },
+        "output": { "device_id": "default", "volume": 150, "available_devices": [] },
+        "mode": {
+            "type": "VOICE_ACTIVITY",
+            "auto_threshold": true,
+            "threshold": -60,
+            "shortcut": [],
+            "delay": 20
+        },
+        "automatic_gain_control": true,
+        "echo_cancellation": true,
+        "noise_suppression": true,
+        "qos": false,
+        "silence_warning": true,
+        "deaf": false,
+        "mute": false
+    });
+    let server = MockServer::start().await.unwrap();
+    server.respond_to("GET_VOICE_SETTINGS", MockResponse::Data(settings.clone()));
+    server.respond_to("SET_VOICE_SETTINGS", MockResponse::Data(settings));
+    let client = connect(&server).await;
+
+    let previous = client.get_voice_settings().await.unwrap();
+    assert_eq!(previous.output.volume, 150.0);
+    assert_eq!(previous.mode.mode_type, VoiceModeType::VoiceActivity);
+
+    client
+        .set_voice_settings(
+            SetVoiceSettingsArgs::new()
+                .mute(true)
+                .output(SetVoiceSettingsIo::new().volume(20.0)),
+        )
+        .await
+        .unwrap();
+    client.set_voice_settings((&previous).into()).await.unwrap();
+
+    let commands = server.commands_named("SET_VOICE_SETTINGS");
+    assert_eq!(
+        commands[0]["args"],
+        json!({ "output": { "volume": 20.0 }, "mute": true })
+    );
+    assert_eq!(commands[1]["args"]["output"]["volume"], 150.0);
+    assert_eq!(commands[1]["args"]["mode"]["type"], "VOICE_ACTIVITY");
+    assert_eq!(commands[1]["args"]["mute"], false);
+}
+
+#[tokio::test]
+async fn avatar_is_fetched_as_data_uri() {
+    let server = MockServer::start().await.unwrap();
+    server.respond_to(
+        "GET_IMAGE",
+        MockResponse::Data(json!({ "data_uri": "data:image/webp;base64,UklGR" })),
+    );
+    let client = connect(&server).await;
+
+    let user_id = client.user().id;
+    let avatar = client
+        .get_image(
+            GetImageArgs::user(&user_id)
+                .format(ImageFormat::Webp)
+                .size(64),
+        )
+        .await
+        .unwrap();
+    assert_eq!(avatar, "data:image/webp;base64,UklGR");
+
+    let commands = server.commands_named("GET_IMAGE");
+    assert_eq!(
+        commands[0]["args"],
+        json!({ "type": "user", "id": user_id, "format": "webp", "size": 64 })
+    );
+}
+
+#[tokio::test]
+async fn join_requests_can_be_accepted() {
+    let server = MockServer::start().await.unwrap();
+    let client = connect(&server).await;
+
+    let activity = Activity::new()
+        .details("Frieren".to_string())
+        .party(ActivityParty::new().id("party".to_string()).size((1, 4)))
+        .secrets(ActivitySecrets::new().join("join-secret".to_string()));
+    client
+        .request(&RPCCommand::SetActivity(SetActivityArgs::new(activity)))
+        .await
+        .unwrap();
+    assert_eq!(
+        server.commands_named("SET_ACTIVITY")[0]["args"]["activity"]["party"],
+        json!({ "id": "party", "size": [1, 4] })
+    );
+
+    let mut requests = client
+        .subscribe(RPCEvent::ActivityJoinRequest)
+        .await
+        .unwrap();
+    let mut joins = client.subscribe(RPCEvent::ActivityJoin).await.unwrap();
+
+    server.send_event(
+        "ACTIVITY_JOIN_REQUEST",
+        json!({
+            "user": {
+                "id": "190320984123768832",
+                "username": "friend",
+                "discriminator": "7479",
+                "avatar": null
+            }
+        }),
+    );
+    let user_id = match requests.next().await {
+        Some(EventPayload::ActivityJoinRequest { data }) => data.user.id,
+        other => panic!("expected ACTIVITY_JOIN_REQUEST, got {:?}", other),
+    };
+    client.accept_join_request(&user_id).await.unwrap();
+    assert_eq!(
+        server.commands_named("SEND_ACTIVITY_JOIN_INVITE")[0]["args"],
+        json!({ "user_id": "190320984123768832" })
+    );
+
+    server.send_event("ACTIVITY_JOIN", json!({ "secret": "join-secret" }));
+    match joins.next().await {
+        Some(EventPayload::ActivityJoin { data }) => assert_eq!(data.secret, "join-secret"),
+        other => panic!("expected ACTIVITY_JOIN, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn presence_queue_coalesces_and_skips_duplicates() {
+    let server = MockServer::start().await.unwrap();
+    let client = connect(&server).await;
+    let queue = client.presence_queue(RateLimit::new(1, Duration::from_millis(200)));
+    let activity = |details: &str| Activity::new().details(details.to_string());
+
+    queue.set(activity("Episode 1")).unwrap();
+    queue.flush().await;
+
+    // the slot is taken, so these wait and only the latest is sent
+    queue.set(activity("Episode 2")).unwrap();
+    queue.set(activity("Episode 3")).unwrap();
+    let report = queue.flush().await;
+    assert_eq!(
+        report.delivered.unwrap().activity,
+        Some(activity("Episode 3"))
+    );
+
+    queue.set(activity("Episode 3")).unwrap();
+    let report = queue.flush().await;
+    assert!(!report.pending);
+    assert!(report.last_error.is_none());
+
+    let details: Vec<_> = server
+        .commands_named("SET_ACTIVITY")
+        .iter()
+        .map(|command| command["args"]["activity"]["details"].clone())
+        .collect();
+    assert_eq!(details, [json!("Episode 1"), json!("Episode 3")]);
+}
+
+#[tokio::test]
+async fn presence_queue_sends_updates_queued_during_a_send() {
+    let server = MockServer::start().await.unwrap();
+    // keeps the first update in flight until it times out
+    server.respond_to("SET_ACTIVITY", MockResponse::Ignore);
+    let client = DiscordIPCClient::builder(CLIENT_ID)
+        .socket_path(server.path())
+        .request_timeout(Duration::from_millis(200))
+        .connect()
+        .await
+        .unwrap();
+    let queue = client.presence_queue(RateLimit::default());
+    let activity = |details: &str| Activity::new().details(details.to_string());
+
+    queue.set(activity("Episode 1")).unwrap();
+    server.wait_for_commands("SET_ACTIVITY", 1).await;
+    server.respond_to("SET_ACTIVITY", MockResponse::Data(json!({})));
+    queue.set(activity("Episode 2")).unwrap();
+
+    let report = queue.flush().await;
+    assert!(!report.pending);
+    assert_eq!(
+        report.delivered.unwrap().activity,
+        Some(activity("Episode 2"))
+    );
+    assert_eq!(server.commands_named("SET_ACTIVITY").len(), 2);
+}
+
+#[tokio::test]
+async fn presence_queue_respects_the_rate_limit() {
+    let server = MockServer::start().await.unwrap();
+    let client = connect(&server).await;
+    let per = Duration::from_millis(300);
+    let queue = client.presence_queue(RateLimit::new(2, per));
+
+    let start = Instant::now();
+    for i in 1..=3 {
+        queue
+            .set(Activity::new().details(format!("Episode {}", i)))
+            .unwrap();
+        queue.flush().await;
+    }
+
+    assert!(start.elapsed() >= per);
+    assert_eq!(server.commands_named("SET_ACTIVITY").len(), 3);
+}
+
+#[tokio::test]
+async fn close_clears_activity_and_stops_streams() {
+    let server = MockServer::start().await.unwrap();
+    let client = connect(&server).await;
+
+    client.request(&set_activity("Frieren")).await.unwrap();
+    let mut stream = client
+        .subscribe(RPCEvent::VoiceChannelSelect)
+        .await
+        .unwrap();
+
+    client.close().await.unwrap();
+
+    let commands = server.commands_named("SET_ACTIVITY");
+    assert_eq!(commands.len(), 2);
+    assert!(commands[1]["args"].get("activity").is_none());
+    server
+        .wait_until(|server| {
+            server
+                .frames()
+                .iter()
+                .any(|frame| frame.opcode == OPCODES::Close as u32)
+        })
+        .await;
+
+    assert!(stream.next().await.is_none());
+    assert!(matches!(
+        client.request(&set_activity("Frieren")).await,
+        Err(DiscordRPCError::ClientClosed)
+    ));
+    // closing twice is fine
+    client.close().await.unwrap();
+}
@@ -0,0 +1,88 @@
+use discord_rpc::errors::DiscordRPCError;
+use discord_rpc::opcodes::OPCODES;
+use discord_rpc::protocol::{encode_frame, Frame, FrameDecoder, HEADER_SIZE};
+use proptest::prelude::*;
+
+fn opcode() -> impl Strategy<Value = OPCODES> {
+    prop_oneof![
+        Just(OPCODES::Handshake),
+        Just(OPCODES::Frame),
+        Just(OPCODES::Close),
+        Just(OPCODES::Ping),
+        Just(OPCODES::Pong),
+    ]
+}
+
+fn frame() -> impl Strategy<Value = Frame> {
+    (opcode(), ".{0,64}").prop_map(|(opcode, payload)| Frame::new(opcode, payload))
+}
+
+/// Feeds `bytes` in chunks of `chunk` bytes, collecting every frame.
+fn decode_chunked(decoder: &mut FrameDecoder, bytes: &[u8], chunk: usize) -> Vec<Frame> {
+    let mut frames = Vec::new();
+    for part in bytes.chunks(chunk) {
+        decoder.push(part);
+        while let Some(frame) = decoder.decode().unwrap() {
+            frames.push(frame);
+        }
+    }
+    frames
+}
+
+proptest! {
+    #[test]
+    fn frames_survive_any_chunking(frames in prop::collection::vec(frame(), 0..8), chunk in 1usize..32) {
+        let bytes: Vec<u8> = frames.iter().flat_map(Frame::encode).collect();
+        let mut decoder = FrameDecoder::new();
+
+        prop_assert_eq!(decode_chunked(&mut decoder, &bytes, chunk), frames);
+        prop_assert!(decoder.finish().is_ok());
+    }
+
+    #[test]
+    fn oversized_frames_are_rejected_from_the_header(max in 0usize..1024, extra in 1u32..u32::MAX / 2) {
+        let length = max as u32 + extra;
+        let mut header = (OPCODES::Frame as u32).to_le_bytes().to_vec();
+        header.extend(length.to_le_bytes());
+        let mut decoder = FrameDecoder::with_max_payload(max);
+
+        decoder.push(&header);
+        match decoder.decode() {
+            Err(DiscordRPCError::FrameTooLarge { length: l, max: m }) => {
+                prop_assert_eq!(l, length as usize);
+                prop_assert_eq!(m, max);
+            }
+            other => prop_assert!(false, "expected FrameTooLarge, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn truncated_frames_are_reported(frame in frame(), cut in 1usize..72) {
+        let bytes = frame.encode();
+        let cut = cut.min(bytes.len() - 1);
+        let mut decoder = FrameDecoder::new();
+
+        decoder.push(&bytes[..cut]);
+        prop_assert!(decoder.decode().unwrap().is_none());
+        match decoder.finish() {
+            Err(DiscordRPCError::TruncatedFrame { expected, received }) => {
+                if cut >= HEADER_SIZE {
+                    prop_assert_eq!(expected, bytes.len());
+                }
+                prop_assert_eq!(received, cut);
+            }
+            other => prop_assert!(false, "expected TruncatedFrame, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn payloads_at_the_limit_are_accepted(payload in "[a-z]{0,64}") {
+        let mut decoder = FrameDecoder::with_max_payload(payload.len());
+        decoder.push(&encode_frame(OPCODES::Frame, &payload));
+
+        let frame = decoder.decode().unwrap().unwrap();
+        prop_assert_eq!(frame.payload, payload.clone());
+        prop_assert!(Frame::new(OPCODES::Frame, payload.clone()).encode_bounded(payload.len()).is_ok());
+        prop_assert!(Frame::new(OPCODES::Frame, payload.clone() + "x").encode_bounded(payload.len()).is_err());
+    }
+}
@@ -0,0 +1,89 @@
+#![cfg(unix)]
+
+use discord_rpc::discovery::{connect_selected, discover_in, EndpointPreference, ReleaseChannel};
+use discord_rpc::models::commands::{Activity, SetActivityArgs};
+use discord_rpc::models::rpc_command::RPCCommand;
+use discord_rpc::testing::MockServer;
+use serde_json::json;
+
+const CLIENT_ID: &str = "1051728796149096458";
+
+async fn canary_server() -> MockServer {
+    let server = MockServer::start().await.unwrap();
+    server.set_ready_data(json!({
+        "v": 1,
+        "config": {
+            "cdn_host": "cdn.discordapp.com",
+            "api_endpoint": "//canary.discord.com/api",
+            "environment": "production"
+        },
+        "user": {
+            "id": "223456789012345678",
+            "username": "canary",
+            "discriminator": "0",
+            "avatar": null,
+            "avatar_decoration": null
+        }
+    }));
+    server
+}
+
+#[tokio::test]
+async fn discovers_every_live_client() {
+    let stable = MockServer::start().await.unwrap();
+    let canary = canary_server().await;
+    let dead = stable.path().with_file_name("discord-ipc-missing");
+
+    let discovery = discover_in(
+        CLIENT_ID,
+        vec![stable.path().into(), dead.clone(), canary.path().into()],
+    )
+    .await;
+    let endpoints = discovery.endpoints;
+
+    assert_eq!(discovery.skipped.len(), 1);
+    assert_eq!(discovery.skipped[0].0, dead);
+    assert_eq!(endpoints.len(), 2);
+    assert_eq!(endpoints[0].release_channel(), ReleaseChannel::Stable);
+    assert_eq!(endpoints[1].release_channel(), ReleaseChannel::Canary);
+    assert_eq!(endpoints[1].user().username, "canary");
+    assert_eq!(endpoints[1].environment(), "production");
+
+    let selected = EndpointPreference::Channel(ReleaseChannel::Canary).select(&endpoints);
+    assert_eq!(selected, [&endpoints[1]]);
+    let selected = EndpointPreference::Channel(ReleaseChannel::Ptb).select(&endpoints);
+    assert_eq!(selected, [&endpoints[0]]);
+}
+
+#[tokio::test]
+async fn mirrors_activity_to_all_clients() {
+    let stable = MockServer::start().await.unwrap();
+    let canary = canary_server().await;
+    let discovery = discover_in(CLIENT_ID, vec![stable.path().into(), canary.path().into()]).await;
+
+    let mut clients = connect_selected(CLIENT_ID, &EndpointPreference::All, &discovery.endpoints)
+        .await
+        .unwrap();
+    assert_eq!(clients.len(), 2);
+
+    let activity = Activity::new().details("Frieren".to_string());
+    let command = RPCCommand::SetActivity(SetActivityArgs::new(activity));
+    for client in &mut clients {
+        client.request(&command).await.unwrap();
+    }
+
+    assert_eq!(stable.commands_named("SET_ACTIVITY").len(), 1);
+    assert_eq!(canary.commands_named("SET_ACTIVITY").len(), 1);
+}
+
+#[test]
+fn preference_round_trips_through_json() {
+    let preference = EndpointPreference::Channel(ReleaseChannel::Canary);
+    let json = serde_json::to_value(&preference).unwrap();
+
+    assert_eq!(json, json!({ "kind": "channel", "value": "canary" }));
+    assert_eq!(
+        serde_json::from_value::<EndpointPreference>(json).unwrap(),
+        preference
+    );
+}
@@ -0,0 +1,169 @@
+use discord_rpc::errors::{DiscordRPCError, RpcErrorCode};
+use discord_rpc::models::commands::EventFunctionPayload;
+use discord_rpc::models::rpc_command::RPCCommand;
+use discord_rpc::opcodes::OPCODES;
+use discord_rpc::protocol::{encode_frame, FrameDecoder, Session, SessionEvent, SessionState};
+use serde_json::{json, Value};
+
+const CLIENT_ID: &str = "1051728796149096458";
+
+fn ready_frame() -> Vec<u8> {
+    let ready = json!({
+        "cmd": "DISPATCH",
+        "evt": "READY",
+        "data": {
+            "v": 1,
+            "config": {
+                "cdn_host": "cdn.discordapp.com",
+                "api_endpoint": "//discord.com/api",
+                "environment": "production"
+            },
+            "user": {
+                "id": "123456789012345678",
+                "username": "mock",
+                "discriminator": "0001",
+                "avatar": null,
+                "avatar_decoration": null
+            }
+        }
+    });
+    encode_frame(OPCODES::Frame, &ready.to_string())
+}
+
+/// Decodes every frame the session queued for writing.
+fn written(session: &mut Session) -> Vec<(OPCODES, Value)> {
+    let mut decoder = FrameDecoder::new();
+    decoder.push(&session.take_outgoing());
+    let mut frames = Vec::new();
+    while let Some(frame) = decoder.decode().unwrap() {
+        frames.push((frame.opcode, serde_json::from_str(&frame.payload).unwrap()));
+    }
+    frames
+}
+
+fn ready_session() -> Session {
+    let mut session = Session::new(CLIENT_ID);
+    session.handshake();
+    session.take_outgoing();
+    session.receive(&ready_frame());
+    session.poll().unwrap();
+    session
+}
+
+#[test]
+fn decoder_waits_for_complete_frames() {
+    let bytes = encode_frame(OPCODES::Frame, "{\"evt\":null}");
+    let mut decoder = FrameDecoder::new();
+
+    for byte in &bytes[..bytes.len() - 1] {
+        decoder.push(&[*byte]);
+        assert!(decoder.decode().unwrap().is_none());
+    }
+    decoder.push(&bytes[bytes.len() - 1..]);
+
+    let frame = decoder.decode().unwrap().unwrap();
+    assert_eq!(frame.opcode, OPCODES::Frame);
+    assert_eq!(frame.payload, "{\"evt\":null}");
+}
+
+#[test]
+fn session_handshakes_and_answers_pings() {
+    let mut session = Session::new(CLIENT_ID);
+    session.handshake();
+    assert_eq!(
+        written(&mut session),
+        [(
+            OPCODES::Handshake,
+            json!({ "v": 1, "client_id": CLIENT_ID })
+        )]
+    );
+
+    let mut bytes = encode_frame(OPCODES::Ping, "{\"ping\":1}");
+    bytes.extend(ready_frame());
+    session.receive(&bytes);
+
+    match session.poll().unwrap() {
+        Some(SessionEvent::Ready(ready)) => assert_eq!(ready.user.username, "mock"),
+        other => panic!("expected READY, got {:?}", other),
+    }
+    assert_eq!(session.state(), SessionState::Ready);
+    assert_eq!(
+        written(&mut session),
+        [(OPCODES::Pong, json!({ "ping": 1 }))]
+    );
+}
+
+#[test]
+fn session_matches_responses_by_nonce() {
+    let mut session = ready_session();
+    let nonce = session.send_command(&RPCCommand::GetGuilds).unwrap();
+    let frames = written(&mut session);
+    assert_eq!(frames[0].1["cmd"], "GET_GUILDS");
+    assert_eq!(frames[0].1["nonce"], nonce.as_str());
+
+    let event = json!({ "cmd": "DISPATCH", "evt": "SPEAKING_START", "data": {} });
+    let response = json!({ "cmd": "GET_GUILDS", "data": { "guilds": [] }, "nonce": nonce });
+    let mut bytes = encode_frame(OPCODES::Frame, &event.to_string());
+    bytes.extend(encode_frame(OPCODES::Frame, &response.to_string()));
+    session.receive(&bytes);
+
+    assert!(matches!(
+        session.poll().unwrap(),
+        Some(SessionEvent::Event(_))
+    ));
+    match session.poll().unwrap() {
+        Some(SessionEvent::Response { nonce: n, result }) => {
+            assert_eq!(n, nonce);
+            assert!(matches!(result, Ok(EventFunctionPayload::GetGuilds { .. })));
+        }
+        other => panic!("expected a response, got {:?}", other),
+    }
+    assert!(!session.is_pending(&nonce));
+}
+
+#[test]
+fn session_reports_rejected_handshake() {
+    let mut session = Session::new(CLIENT_ID);
+    session.handshake();
+    let close = json!({ "code": 4000, "message": "Invalid Client ID" });
+    session.receive(&encode_frame(OPCODES::Close, &close.to_string()));
+
+    match session.poll() {
+        Err(DiscordRPCError::HandshakeFailed { code, message }) => {
+            assert_eq!(code, 4000);
+            assert_eq!(message, "Invalid Client ID");
+        }
+        other => panic!("expected a handshake error, got {:?}", other),
+    }
+    assert_eq!(session.state(), SessionState::Closed);
+}
+
+#[test]
+fn session_types_error_responses() {
+    let mut session = ready_session();
+    let nonce = session.send_command(&RPCCommand::GetGuilds).unwrap();
+    session.take_outgoing();
+
+    let error = json!({
+        "cmd": "GET_GUILDS",
+        "evt": "ERROR",
+        "data": { "code": 4006, "message": "Not authenticated or invalid scope" },
+        "nonce": nonce
+    });
+    session.receive(&encode_frame(OPCODES::Frame, &error.to_string()));
+
+    match session.poll().unwrap() {
+        Some(SessionEvent::Response {
+            result: Err(DiscordRPCError::Rpc { code, nonce: n, .. }),
+            ..
+        }) => {
+            assert_eq!(code, RpcErrorCode::InvalidPermissions);
+            assert_eq!(n, Some(nonce));
+        }
+        other => panic!("expected an rpc error, got {:?}", other),
+    }
+
+    assert_eq!(RpcErrorCode::from(4999), RpcErrorCode::Other(4999));
+    assert_eq!(RpcErrorCode::Other(4999).code(), 4999);
+    assert_eq!(RpcErrorCode::from(5003).code(), 5003);
+}
@@ -1,30 +1,63 @@
 use discord_rpc::{
-    models::{
-        commands::{Activity, ActivityAssets, ActivityButton, ActivityTimestamps, SetActivityArgs},
-        rpc_command::RPCCommand,
+    discovery::{self, EndpointPreference, ReleaseChannel},
+    models::commands::{
+        Activity, ActivityAssets, ActivityButton, ActivityTimestamps, ActivityType, GetImageArgs,
+        StatusDisplayType,
     },
-    DiscordIPCClient,
+    ConnectionState, DiscordIPCClient, PresenceQueue, RateLimit,
 };
 use futures::lock::Mutex;
-use serde::Deserialize;
-use tauri::State;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::{async_runtime::JoinHandle, AppHandle, Manager, State};
+
+const CLIENT_ID: &str = "1051728796149096458";
+
+/// Event the connection state of the first instance is emitted under.
+const STATE_EVENT: &str = "discord-state";
+
+/// Length limits discord enforces on the text lines of an activity.
+const MIN_TEXT_LENGTH: usize = 2;
+const MAX_TEXT_LENGTH: usize = 128;
+
+/// A connected Discord instance and the queue its presence goes through.
+pub struct DiscordConnection {
+    /// Shared so requests can be awaited without holding the client list.
+    pub client: Arc<DiscordIPCClient>,
+    pub presence: PresenceQueue,
+    /// Emits the state of the first instance, see [`forward_connection_state`].
+    pub forwarder: Option<JoinHandle<()>>,
+}
 
 pub struct DiscordIntegrationState {
-    pub discord_ipc_client: Mutex<Option<DiscordIPCClient>>,
+    /// One connection per Discord instance the presence is mirrored to.
+    pub discord_ipc_clients: Mutex<Vec<DiscordConnection>>,
+    pub preference: Mutex<EndpointPreference>,
 }
 
 impl Default for DiscordIntegrationState {
     fn default() -> Self {
         Self {
-            discord_ipc_client: Default::default(),
+            discord_ipc_clients: Default::default(),
+            preference: Default::default(),
         }
     }
 }
 
+#[derive(Serialize)]
+pub struct DiscordInstance {
+    pub path: PathBuf,
+    pub channel: ReleaseChannel,
+    pub environment: String,
+    pub username: String,
+}
+
 #[derive(Deserialize)]
 pub struct SetActivityPayload {
     pub animeTitle: String,
     pub animeEpisode: String,
+    pub animeUrl: Option<String>,
 
     pub start: Option<u64>,
     pub end: Option<u64>,
@@ -38,28 +71,107 @@ pub struct SetActivityPayload {
     pub url: Option<String>,
 }
 
+/// Connects to the preferred instances unless already connected.
+async fn ensure_connected(
+    app: &AppHandle,
+    state: &DiscordIntegrationState,
+    ipc_clients: &mut Vec<DiscordConnection>,
+) -> bool {
+    // a client that gave up reconnecting stays closed, connect again instead
+    ipc_clients.retain(|connection| {
+        !matches!(
+            *connection.client.connection_state().borrow(),
+            ConnectionState::Closed { .. }
+        )
+    });
+    if !ipc_clients.is_empty() {
+        return true;
+    }
+
+    let preference = state.preference.lock().await.clone();
+    match discovery::connect_preferred(CLIENT_ID, &preference).await {
+        Ok(clients) => {
+            *ipc_clients = clients
+                .into_iter()
+                .enumerate()
+                .map(|(i, client)| DiscordConnection {
+                    presence: client.presence_queue(RateLimit::default()),
+                    // the titlebar shows the first instance only
+                    forwarder: (i == 0).then(|| forward_connection_state(app, &client)),
+                    client: Arc::new(client),
+                })
+                .collect();
+            true
+        }
+        Err(e) => {
+            println!("Client failed to connect: {}", e);
+            emit_state(
+                app,
+                ConnectionState::Closed {
+                    reason: e.to_string(),
+                },
+            );
+            false
+        }
+    }
+}
+
+/// Emits every state change of `client` until it is closed, so the
+/// titlebar can show whether discord is reachable.
+fn forward_connection_state(app: &AppHandle, client: &DiscordIPCClient) -> JoinHandle<()> {
+    let app = app.clone();
+    let mut states = client.connection_state();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let state = states.borrow_and_update().clone();
+            emit_state(&app, state);
+            if states.changed().await.is_err() {
+                break;
+            }
+        }
+    })
+}
+
+fn emit_state(app: &AppHandle, state: ConnectionState) {
+    if let Err(e) = app.emit_all(STATE_EVENT, state) {
+        println!("Failed to emit discord state: {}", e);
+    }
+}
+
+/// Pads or cuts `text` to discord's limits, so a one letter title or a
+/// very long one does not get the whole activity rejected.
+fn fit_text(text: String) -> String {
+    let mut text: String = text.chars().take(MAX_TEXT_LENGTH).collect();
+    while text.chars().count() < MIN_TEXT_LENGTH {
+        // invisible, so the title reads the same
+        text.push('\u{200b}');
+    }
+    text
+}
+
 pub async fn set_discord_activity(
+    app: &AppHandle,
     state: State<'_, DiscordIntegrationState>,
     payload: Option<SetActivityPayload>,
 ) -> bool {
-    let mut ipc_client_guard = state.discord_ipc_client.lock().await;
-    if ipc_client_guard.as_ref().is_none() {
-        *ipc_client_guard = Some(
-            DiscordIPCClient::new("1051728796149096458")
-                .await
-                .expect("Client failed to connect"),
-        );
+    let mut ipc_clients = state.discord_ipc_clients.lock().await;
+    if !ensure_connected(app, &state, &mut ipc_clients).await {
+        return false;
     }
 
-    let ipc_client = ipc_client_guard.as_mut().unwrap();
-
-    if payload.is_some() {
+    let activity = if payload.is_some() {
         let payload = payload.unwrap();
         let mut activity = Activity::new()
-            .details(payload.animeTitle)
-            .state(payload.animeEpisode)
+            .activity_type(ActivityType::Watching)
+            .status_display_type(StatusDisplayType::Details)
+            .details(fit_text(payload.animeTitle))
+            .state(fit_text(payload.animeEpisode))
             .instance(false);
 
+        if let Some(url) = payload.animeUrl {
+            activity = activity.details_url(url);
+        }
+
         if payload.start.is_some() {
             activity = activity.timestamps(
                 ActivityTimestamps::new()
@@ -72,9 +184,9 @@ pub async fn set_discord_activity(
             activity = activity.assets(
                 ActivityAssets::new()
                     .large_image(payload.largeImage.unwrap())
-                    .large_text(payload.largeImageText.unwrap())
+                    .large_text(fit_text(payload.largeImageText.unwrap()))
                     .small_image(payload.smallImage.unwrap())
-                    .small_text(payload.smallImageText.unwrap()),
+                    .small_text(fit_text(payload.smallImageText.unwrap())),
             );
         }
 
@@ -84,14 +196,115 @@ pub async fn set_discord_activity(
                 .url(payload.url.unwrap())]);
         }
 
-        ipc_client
-            .emit_command(&RPCCommand::SetActivity(SetActivityArgs::new(activity)))
-            .await
-            .is_ok()
+        Some(activity)
     } else {
-        ipc_client
-            .emit_command(&RPCCommand::SetActivity(SetActivityArgs::default()))
-            .await
-            .is_ok()
+        None
+    };
+
+    // the queues send within discord's rate limit, so rapid seeking
+    // ends on the latest state instead of dropping updates
+    let mut all_queued = true;
+    for connection in ipc_clients.iter() {
+        match activity.clone() {
+            Some(activity) => {
+                if let Err(e) = connection.presence.set(activity) {
+                    println!("Failed to set activity: {}", e);
+                    all_queued = false;
+                }
+            }
+            None => connection.presence.clear(),
+        }
+    }
+    all_queued
+}
+
+/// Returns the avatar of the user logged into the first connected instance
+/// as a `data:` uri, fetched through discord so no request goes to the cdn.
+pub async fn get_discord_avatar(
+    app: &AppHandle,
+    state: &DiscordIntegrationState,
+    size: u32,
+) -> Option<String> {
+    let client = {
+        let mut ipc_clients = state.discord_ipc_clients.lock().await;
+        if !ensure_connected(app, state, &mut ipc_clients).await {
+            return None;
+        }
+        ipc_clients.first()?.client.clone()
+    };
+
+    // the list is unlocked, so activity updates do not wait for the image
+    let user_id = client.user().id;
+    match client
+        .get_image(GetImageArgs::user(&user_id).size(size))
+        .await
+    {
+        Ok(data_uri) => Some(data_uri),
+        Err(e) => {
+            println!("Failed to get discord avatar: {}", e);
+            None
+        }
+    }
+}
+
+pub async fn list_discord_instances() -> Vec<DiscordInstance> {
+    match discovery::discover(CLIENT_ID).await {
+        Ok(discovery) => {
+            for (path, e) in &discovery.skipped {
+                println!("Skipping discord instance at {}: {}", path.display(), e);
+            }
+            discovery
+                .endpoints
+                .into_iter()
+                .map(|endpoint| DiscordInstance {
+                    channel: endpoint.release_channel(),
+                    environment: endpoint.environment().to_string(),
+                    username: endpoint.user().username.clone(),
+                    path: endpoint.path,
+                })
+                .collect()
+        }
+        Err(e) => {
+            println!("Could not find any discord instance: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Switches the instances the presence goes to. If the preference changed,
+/// the current clients are closed and the next activity connects according
+/// to `preference`.
+pub async fn set_discord_preference(
+    state: &DiscordIntegrationState,
+    preference: EndpointPreference,
+) {
+    {
+        let mut current = state.preference.lock().await;
+        if *current == preference {
+            return;
+        }
+        *current = preference;
+    }
+    close_discord_client(state).await;
+}
+
+pub async fn close_discord_client(state: &DiscordIntegrationState) {
+    let ipc_clients = std::mem::take(&mut *state.discord_ipc_clients.lock().await);
+    for DiscordConnection {
+        client,
+        presence,
+        forwarder,
+    } in ipc_clients
+    {
+        // the next connection forwards its own state, so this one must not
+        // report the close after it
+        if let Some(forwarder) = forwarder {
+            forwarder.abort();
+        }
+        // anything still queued is superseded by clearing the activity on close
+        drop(presence);
+        if let Err(e) = client.close().await {
+            println!("Failed to close discord client: {}", e);
+        }
     }
 }
@@ -5,20 +5,48 @@
 
 mod app;
 mod commands;
-use commands::set_activity::{set_discord_activity, DiscordIntegrationState, SetActivityPayload};
-use tauri::{State, WindowBuilder, WindowUrl};
+use commands::set_activity::{
+    close_discord_client, get_discord_avatar, list_discord_instances, set_discord_activity,
+    set_discord_preference, DiscordInstance, DiscordIntegrationState, SetActivityPayload,
+};
+use discord_rpc::discovery::EndpointPreference;
+use tauri::{AppHandle, Manager, RunEvent, State, WindowBuilder, WindowUrl};
 use tauri_plugin_store;
 use window_shadows::set_shadow;
 
 #[tauri::command]
 async fn set_activity(
+    app: AppHandle,
     state: State<'_, DiscordIntegrationState>,
     payload: Option<SetActivityPayload>,
 ) -> Result<bool, ()> {
-    let a = set_discord_activity(state, payload).await;
+    let a = set_discord_activity(&app, state, payload).await;
     Ok(a)
 }
 
+#[tauri::command]
+async fn list_discord_clients() -> Result<Vec<DiscordInstance>, ()> {
+    Ok(list_discord_instances().await)
+}
+
+#[tauri::command]
+async fn set_discord_client(
+    state: State<'_, DiscordIntegrationState>,
+    preference: EndpointPreference,
+) -> Result<(), ()> {
+    set_discord_preference(&state, preference).await;
+    Ok(())
+}
+
+#[tauri::command]
+async fn discord_avatar(
+    app: AppHandle,
+    state: State<'_, DiscordIntegrationState>,
+    size: u32,
+) -> Result<Option<String>, ()> {
+    Ok(get_discord_avatar(&app, &state, size).await)
+}
+
 fn main() {
     tauri::Builder::default()
         .plugin(app::window_state::Builder::default().build())
@@ -43,7 +71,19 @@ fn main() {
             Ok(())
         })
         .manage(DiscordIntegrationState::default())
-        .invoke_handler(tauri::generate_handler![set_activity])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .invoke_handler(tauri::generate_handler![
+            set_activity,
+            list_discord_clients,
+            set_discord_client,
+            discord_avatar
+        ])
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            if let RunEvent::Exit = event {
+                // clear the presence right away instead of letting discord time it out
+                let state = app.state::<DiscordIntegrationState>();
+                tauri::async_runtime::block_on(close_discord_client(&state));
+            }
+        });
 }
@@ -1,8 +1,24 @@
 import { invoke } from "@tauri-apps/api";
+import { listen } from "@tauri-apps/api/event";
+import { getDiscordPreference, saveDiscordPreference } from "../util/store";
+
+export interface DiscordClient {
+	path: string;
+	channel: DiscordReleaseChannel;
+	environment: string;
+	username: string;
+}
+
+/** mirrors `ConnectionState` of the discord_rpc crate */
+export type DiscordConnectionState =
+	| { state: "ready"; user: { id: string; username: string } }
+	| { state: "reconnecting"; reason: string; attempts: number }
+	| { state: "closed"; reason: string };
 
 interface SetActivityPayload {
 	animeTitle: string; //details
 	animeEpisode: string; //state
+	animeUrl?: string; //detailsUrl
 
 	start?: number; //startTimestamp
 	end?: number; //endTimeStamp
@@ -23,9 +39,14 @@ export const setActivity = (payload: {
 	image: string;
 	title: string;
 	episode: string;
+	url?: string;
 }) => {
 	const now = Date.now();
-	const activity: SetActivityPayload = { animeTitle: payload.title, animeEpisode: payload.episode };
+	const activity: SetActivityPayload = {
+		animeTitle: payload.title,
+		animeEpisode: payload.episode,
+		animeUrl: payload.url,
+	};
 
 	if (payload.isPlaying) {
 		activity.start = now + payload.progress * 1000;
@@ -44,3 +65,23 @@ export const setActivity = (payload: {
 export const clearActivity = () => {
 	invoke("set_activity").then(() => console.log("Cleared activity"));
 };
+
+export const listDiscordClients = () => invoke<Array<DiscordClient>>("list_discord_clients");
+
+export const setDiscordClient = async (preference: DiscordPreference) => {
+	await saveDiscordPreference(preference);
+	await invoke("set_discord_client", { preference });
+};
+
+/** avatar of the connected discord user as a data uri, null if discord is not running */
+export const getDiscordAvatar = (size = 64) => invoke<string | null>("discord_avatar", { size });
+
+/** calls `handler` whenever the connection to discord changes, resolves to the unlisten function */
+export const onDiscordState = (handler: (state: DiscordConnectionState) => void) =>
+	listen<DiscordConnectionState>("discord-state", (event) => handler(event.payload));
+
+/** hands the saved client preference to the backend, call once on startup */
+export const restoreDiscordClient = async () => {
+	const preference = await getDiscordPreference();
+	if (preference !== null) await invoke("set_discord_client", { preference });
+};
@@ -0,0 +1,74 @@
+import { useEffect, useState } from "preact/hooks";
+import { DiscordClient, listDiscordClients, setDiscordClient } from "../api/discord";
+import { getDiscordPreference } from "../util/store";
+
+const CHANNEL_NAMES: Record<DiscordReleaseChannel, string> = {
+	stable: "Stable",
+	ptb: "PTB",
+	canary: "Canary",
+	development: "Development",
+};
+
+interface Choice {
+	label: string;
+	preference: DiscordPreference;
+}
+
+/** option values have to be strings, so preferences are compared as json */
+const key = (preference: DiscordPreference) => JSON.stringify(preference);
+
+const choices = (clients: Array<DiscordClient>, current: DiscordPreference): Array<Choice> => {
+	const list: Array<Choice> = [{ label: "First found", preference: { kind: "first" } }];
+	for (const client of clients) {
+		list.push({
+			label: `${CHANNEL_NAMES[client.channel]} (${client.username})`,
+			preference: { kind: "channel", value: client.channel },
+		});
+	}
+	list.push({ label: "All clients", preference: { kind: "all" } });
+
+	// keep the saved choice visible while that client is not running
+	if (!list.some((choice) => key(choice.preference) === key(current)) && current.kind === "channel") {
+		list.push({ label: `${CHANNEL_NAMES[current.value]} (not running)`, preference: current });
+	}
+	return list;
+};
+
+/** lets the user pick which running discord clients (stable, ptb, canary) show the presence */
+export const DiscordPicker = () => {
+	const [clients, setClients] = useState<Array<DiscordClient>>([]);
+	const [current, setCurrent] = useState<DiscordPreference>({ kind: "first" });
+
+	const refresh = () => listDiscordClients().then(setClients);
+
+	useEffect(() => {
+		getDiscordPreference().then((preference) => {
+			if (preference !== null) setCurrent(preference);
+		});
+		refresh();
+	}, []);
+
+	const list = choices(clients, current);
+
+	return (
+		<select
+			class="titlebar-discord-picker"
+			title="Discord client to show the presence on"
+			value={key(current)}
+			// clients may have been started or closed since the last look
+			onFocus={refresh}
+			onChange={(event) => {
+				const choice = list.find((choice) => key(choice.preference) === event.currentTarget.value);
+				if (choice === undefined) return;
+				setCurrent(choice.preference);
+				setDiscordClient(choice.preference);
+			}}
+		>
+			{list.map((choice) => (
+				<option key={key(choice.preference)} value={key(choice.preference)}>
+					{choice.label}
+				</option>
+			))}
+		</select>
+	);
+};
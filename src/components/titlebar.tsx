@@ -1,10 +1,50 @@
 import { window } from "@tauri-apps/api";
+import { useEffect, useState } from "preact/hooks";
+import { DiscordConnectionState, getDiscordAvatar, onDiscordState } from "../api/discord";
 import "../styles/titlebar.css";
 import { onWindowClose } from "../util/lifecycle";
+import { DiscordPicker } from "./discordPicker";
+
+const discordStatus = (discord: DiscordConnectionState | null) => {
+	switch (discord?.state) {
+		case "ready":
+			return `Discord: connected as ${discord.user.username}`;
+		case "reconnecting":
+			return "Discord: connecting…";
+		default:
+			return "Discord: not running";
+	}
+};
 
 export const Titlebar = () => {
+	const [avatar, setAvatar] = useState<string | null>(null);
+	const [discord, setDiscord] = useState<DiscordConnectionState | null>(null);
+
+	useEffect(() => {
+		const unlisten = onDiscordState((state) => {
+			setDiscord(state);
+			// the user may have changed after a reconnect
+			if (state.state === "ready") getDiscordAvatar().then(setAvatar);
+			else if (state.state === "closed") setAvatar(null);
+		});
+		getDiscordAvatar().then(setAvatar);
+		return () => {
+			unlisten.then((f) => f());
+		};
+	}, []);
+
 	return (
 		<div data-tauri-drag-region class="titlebar" id="titlebar">
+			<div
+				data-tauri-drag-region
+				class={`titlebar-discord titlebar-discord-${discord?.state ?? "closed"}`}
+				title={discord && "reason" in discord ? discord.reason : undefined}
+			>
+				{avatar && <img class="titlebar-avatar" src={avatar} alt="discord avatar" />}
+				{discordStatus(discord)}
+				<DiscordPicker />
+			</div>
+
 			<div
 				class="titlebar-button"
 				id="titlebar-minimize"
@@ -7,6 +7,7 @@ import { AppProvider } from "./components/app";
 import { Episodes } from "./pages/episodes";
 import { window } from "@tauri-apps/api";
 import { onWindowClose } from "./util/lifecycle";
+import { restoreDiscordClient } from "./api/discord";
 
 const crumb = (match: BreadcrumbMatch) => {
 	const text = (match.handle.key !== undefined ? match.params[match.handle.key] : match.id)!.toUpperCase();
@@ -41,6 +42,8 @@ const App = () => {
 
 render(<App />, document.getElementById("app") as HTMLElement);
 
+restoreDiscordClient();
+
 window.getCurrent().onCloseRequested(async () => {
 	await onWindowClose();
 });
@@ -119,6 +119,7 @@ export const Episodes = () => {
 					image: currentAnime.coverImage ?? "",
 					progress: Math.floor(video?.currentTime ?? 0),
 					title: currentAnime.title.romaji ?? "",
+					url: `https://anilist.co/anime/${currentAnime.anilistId}`,
 				});
 			};
 
@@ -130,6 +131,7 @@ export const Episodes = () => {
 					image: currentAnime.coverImage ?? "",
 					progress: Math.floor(video?.currentTime ?? 0),
 					title: currentAnime.title.romaji ?? "",
+					url: `https://anilist.co/anime/${currentAnime.anilistId}`,
 				});
 			};
 		}
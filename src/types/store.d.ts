@@ -16,3 +16,12 @@ type RecentlyWatched = {
 type PlanToWatch = {
 	date: string;
 };
+
+type DiscordReleaseChannel = "stable" | "ptb" | "canary" | "development";
+
+/** which running discord clients receive the presence */
+type DiscordPreference =
+	| { kind: "first" }
+	| { kind: "channel"; value: DiscordReleaseChannel }
+	| { kind: "path"; value: string }
+	| { kind: "all" };
@@ -20,6 +20,17 @@ export const setPlanToWatch = async (id: EnimeAnimeId, plan?: PlanToWatch) => {
 	}
 };
 
+const settingsStore = new Store(".settings.dat");
+
+export const getDiscordPreference = async (): Promise<DiscordPreference | null> => {
+	return await settingsStore.get<DiscordPreference>("discordClient");
+};
+
+export const saveDiscordPreference = async (preference: DiscordPreference) => {
+	await settingsStore.set("discordClient", preference);
+	await settingsStore.save();
+};
+
 const playbackProgressStore = new Store(".playback-progress.dat");
 
 export const getRecentlyWatched = async (): Promise<Array<RecentlyWatched>> => {